@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use ethers::abi::{AbiParser, Component, Item, ParamType, RawAbi};
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static::lazy_static! {
+    static ref MODIFIERS: Regex = Regex::new(r"\s+(virtual\b|override\b(\s*\([^)]*\))?)").unwrap();
+}
+
+pub fn abi_to_solidity(abi: RawAbi, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut structs: Vec<(String, String)> = Vec::new();
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    let mut functions = Vec::new();
+
+    for item in abi {
+        match item.type_field.as_str() {
+            "function" => functions.push(format_function(&item, &mut structs)?),
+            "event" => events.push(format_event(&item, &mut structs)?),
+            "error" => errors.push(format!(
+                "error {}({});",
+                item_name(&item)?,
+                format_params(&item.inputs, None, &mut structs)?
+            )),
+            "fallback" => {
+                let payable = item.state_mutability.as_deref() == Some("payable");
+                functions.push(format!("fallback() external{};", if payable { " payable" } else { "" }));
+            }
+            "receive" => functions.push("receive() external payable;".to_string()),
+            // Interfaces cannot declare constructors
+            "constructor" => {}
+            other => return Err(format!("Unsupported ABI item type: {}", other).into()),
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("// SPDX-License-Identifier: MIT\n");
+    out.push_str("pragma solidity ^0.8.0;\n\n");
+    out.push_str(&format!("interface {} {{\n", name));
+
+    let sections: Vec<Vec<String>> = vec![
+        structs.into_iter().map(|(_, definition)| definition).collect(),
+        events,
+        errors,
+        functions,
+    ];
+    let mut first = true;
+    for section in sections.into_iter().filter(|s| !s.is_empty()) {
+        if !first {
+            out.push('\n');
+        }
+        first = false;
+        for line in section {
+            for l in line.lines() {
+                out.push_str("    ");
+                out.push_str(l);
+                out.push('\n');
+            }
+        }
+    }
+    out.push_str("}\n");
+
+    Ok(out)
+}
+
+fn item_name(item: &Item) -> Result<&str, Box<dyn std::error::Error>> {
+    item.name
+        .as_deref()
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| format!("ABI {} entry is missing a name", item.type_field).into())
+}
+
+fn format_function(item: &Item, structs: &mut Vec<(String, String)>) -> Result<String, Box<dyn std::error::Error>> {
+    let mut line = format!(
+        "function {}({}) external",
+        item_name(item)?,
+        format_params(&item.inputs, Some("calldata"), structs)?
+    );
+    match item.state_mutability.as_deref() {
+        Some("view") => line.push_str(" view"),
+        Some("pure") => line.push_str(" pure"),
+        Some("payable") => line.push_str(" payable"),
+        _ => {}
+    }
+    if !item.outputs.is_empty() {
+        line.push_str(&format!(" returns ({})", format_params(&item.outputs, Some("memory"), structs)?));
+    }
+    line.push(';');
+    Ok(line)
+}
+
+fn format_event(item: &Item, structs: &mut Vec<(String, String)>) -> Result<String, Box<dyn std::error::Error>> {
+    let params = item
+        .inputs
+        .iter()
+        .map(|c| {
+            let mut param = component_type(c, structs)?;
+            if c.indexed.unwrap_or(false) {
+                param.push_str(" indexed");
+            }
+            if !c.name.is_empty() {
+                param.push(' ');
+                param.push_str(&c.name);
+            }
+            Ok(param)
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+
+    let anonymous = if item.anonymous.unwrap_or(false) { " anonymous" } else { "" };
+    Ok(format!("event {}({}){};", item_name(item)?, params.join(", "), anonymous))
+}
+
+fn format_params(
+    params: &[Component],
+    location: Option<&str>,
+    structs: &mut Vec<(String, String)>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let params = params
+        .iter()
+        .map(|c| {
+            let mut param = component_type(c, structs)?;
+            if let Some(location) = location {
+                if is_reference_type(&c.type_field) {
+                    param.push(' ');
+                    param.push_str(location);
+                }
+            }
+            if !c.name.is_empty() {
+                param.push(' ');
+                param.push_str(&c.name);
+            }
+            Ok(param)
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    Ok(params.join(", "))
+}
+
+fn is_reference_type(type_field: &str) -> bool {
+    type_field == "string" || type_field == "bytes" || type_field.starts_with("tuple") || type_field.ends_with(']')
+}
+
+// Resolves the Solidity type of a component, registering struct definitions for tuples
+fn component_type(component: &Component, structs: &mut Vec<(String, String)>) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(suffix) = component.type_field.strip_prefix("tuple") else {
+        return Ok(component.type_field.clone());
+    };
+
+    let struct_name = component
+        .internal_type
+        .as_deref()
+        .and_then(|t| t.strip_prefix("struct "))
+        .map(|t| t.split('[').next().unwrap_or(t))
+        .map(|t| t.rsplit('.').next().unwrap_or(t).to_string())
+        .unwrap_or_else(|| format!("Struct{}", structs.len()));
+
+    if !structs.iter().any(|(name, _)| *name == struct_name) {
+        // Reserve the name before recursing so self-referencing names are not registered twice
+        structs.push((struct_name.clone(), String::new()));
+        let mut definition = format!("struct {} {{\n", struct_name);
+        for field in &component.components {
+            let field_name = if field.name.is_empty() { "value".to_string() } else { field.name.clone() };
+            definition.push_str(&format!("    {} {};\n", component_type(field, structs)?, field_name));
+        }
+        definition.push('}');
+        if let Some(entry) = structs.iter_mut().find(|(name, _)| *name == struct_name) {
+            entry.1 = definition;
+        }
+    }
+
+    Ok(format!("{}{}", struct_name, suffix))
+}
+
+pub fn solidity_to_abi(source: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let declarations = extract_declarations(&strip_comments(source))?;
+    if declarations.is_empty() {
+        return Err("No function, event or error declarations found".into());
+    }
+
+    let mut receive = false;
+    let mut fallback = false;
+    let mut lines = Vec::new();
+    for declaration in &declarations {
+        if declaration.starts_with("receive") {
+            receive = true;
+        } else if declaration.starts_with("fallback") {
+            fallback = true;
+        } else if declaration.starts_with("function") && !is_external_function(declaration) {
+            // internal and private functions are not part of the ABI
+        } else {
+            lines.push(clean_declaration(declaration));
+        }
+    }
+
+    let mut parser = AbiParser::default();
+    let mut abi = parser
+        .parse(&lines.iter().map(String::as_str).collect::<Vec<_>>())
+        .map_err(|e| format!("Failed to parse Solidity: {}", e))?;
+    abi.receive = receive;
+    abi.fallback = fallback;
+
+    // Keep the struct names the parser resolved so the ABI carries them as internalType. The parser
+    // records them by function name, so each function is parsed again on its own to tell overloads apart.
+    let mut struct_names = HashMap::new();
+    for line in lines.iter().filter(|line| line.starts_with("function")) {
+        parser.function_params.clear();
+        parser.outputs.clear();
+        let function = parser.parse_function(line).map_err(|e| format!("Failed to parse Solidity: {}", e))?;
+        let inputs = function
+            .inputs
+            .iter()
+            .map(|input| parser.function_params.get(&(function.name.clone(), input.name.clone())).cloned())
+            .collect::<Vec<_>>();
+        // Only the struct names of the outputs are recorded, in order
+        let mut output_structs = parser.outputs.remove(&function.name).unwrap_or_default().into_iter();
+        let outputs = function
+            .outputs
+            .iter()
+            .map(|output| if is_struct(&output.kind) { output_structs.next() } else { None })
+            .collect::<Vec<_>>();
+        struct_names.insert(function.signature(), (inputs, outputs));
+    }
+    for functions in abi.functions.values_mut() {
+        for function in functions {
+            let Some((inputs, outputs)) = struct_names.get(&function.signature()) else {
+                continue;
+            };
+            let params = function.inputs.iter_mut().zip(inputs).chain(function.outputs.iter_mut().zip(outputs));
+            for (param, name) in params {
+                if let Some(name) = name {
+                    param.internal_type = Some(format!("struct {}{}", name, array_suffix(&param.kind)));
+                }
+            }
+        }
+    }
+
+    Ok(serde_json::to_value(&abi)?)
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Walks the source and returns every ABI-relevant declaration collapsed onto a single line.
+// Function bodies are skipped, struct bodies are kept because the parser needs their fields.
+fn extract_declarations(source: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut declarations = Vec::new();
+    let mut current = String::new();
+    let mut chars = source.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                push_declaration(&mut declarations, &current);
+                current.clear();
+            }
+            '{' => {
+                let header = collapse_whitespace(&current);
+                current.clear();
+                if is_container(&header) {
+                    // contract/interface/library bodies are walked like the top level
+                    continue;
+                }
+                let body = read_block(&mut chars)?;
+                if header.starts_with("struct") {
+                    declarations.push(format!("{} {{ {} }}", header, collapse_whitespace(&body)));
+                } else {
+                    push_declaration(&mut declarations, &header);
+                }
+            }
+            '}' => current.clear(),
+            _ => current.push(c),
+        }
+    }
+
+    Ok(declarations)
+}
+
+fn read_block(chars: &mut std::str::Chars) -> Result<String, Box<dyn std::error::Error>> {
+    let mut depth = 1;
+    let mut body = String::new();
+    for c in chars.by_ref() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(body);
+                }
+            }
+            _ => {}
+        }
+        body.push(c);
+    }
+    Err("Unbalanced braces in Solidity source".into())
+}
+
+fn push_declaration(declarations: &mut Vec<String>, raw: &str) {
+    let declaration = collapse_whitespace(raw);
+    let keyword = declaration.split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+    if matches!(keyword, "function" | "event" | "error" | "constructor" | "receive" | "fallback") {
+        declarations.push(declaration);
+    }
+}
+
+fn is_container(header: &str) -> bool {
+    let keyword = header.split_whitespace().find(|w| *w != "abstract").unwrap_or("");
+    matches!(keyword, "contract" | "interface" | "library")
+}
+
+fn is_external_function(declaration: &str) -> bool {
+    !declaration
+        .split(|c: char| c.is_whitespace() || c == ')')
+        .any(|w| w == "internal" || w == "private")
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// The human readable parser only understands visibility, mutability and returns
+fn clean_declaration(declaration: &str) -> String {
+    MODIFIERS.replace_all(declaration, "").to_string()
+}
+
+fn is_struct(kind: &ParamType) -> bool {
+    match kind {
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) => is_struct(inner),
+        ParamType::Tuple(_) => true,
+        _ => false,
+    }
+}
+
+// "[2][]" for a Foo[2][] param, as solc writes it after the struct name in internalType
+fn array_suffix(kind: &ParamType) -> String {
+    match kind {
+        ParamType::Array(inner) => format!("{}[]", array_suffix(inner)),
+        ParamType::FixedArray(inner, size) => format!("{}[{}]", array_suffix(inner), size),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ORACLE: &str = "
+        // SPDX-License-Identifier: MIT
+        interface Oracle {
+            struct Output { bytes32 root; uint128 timestamp; }
+            struct Range { uint256 from; uint256 to; }
+
+            event Posted(bytes32 indexed root, uint256 index);
+
+            function get(uint256 index) external view returns (Output memory);
+            function get(Range calldata range) external view returns (Output[] memory outputs, uint256 count);
+            function set(Output calldata value) external;
+            function set(Range calldata value, uint256 id) external virtual override(A, B);
+            function latest() internal view returns (uint256);
+        }
+    ";
+
+    fn function<'a>(abi: &'a Value, name: &str, input_type: &str) -> &'a Value {
+        abi.as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["name"] == name && item["inputs"][0]["type"] == input_type)
+            .unwrap_or_else(|| panic!("{}({}) not in the ABI", name, input_type))
+    }
+
+    #[test]
+    fn converts_structs_and_overloads() {
+        let abi = solidity_to_abi(ORACLE).unwrap();
+        let items = abi.as_array().unwrap();
+        assert_eq!(items.iter().filter(|item| item["type"] == "function").count(), 4);
+        assert!(items.iter().any(|item| item["type"] == "event" && item["name"] == "Posted"));
+
+        let get = function(&abi, "get", "uint256");
+        assert_eq!(get["outputs"][0]["type"], "tuple");
+        assert_eq!(get["outputs"][0]["internalType"], "struct Output");
+        assert_eq!(get["stateMutability"], "view");
+
+        let get_range = function(&abi, "get", "tuple");
+        assert_eq!(get_range["inputs"][0]["internalType"], "struct Range");
+        assert_eq!(get_range["outputs"][0]["type"], "tuple[]");
+        assert_eq!(get_range["outputs"][0]["internalType"], "struct Output[]");
+        assert_eq!(get_range["outputs"][1]["internalType"], json!(null));
+
+        // Same param name in both overloads, different structs
+        let set = abi.as_array().unwrap().iter().filter(|item| item["name"] == "set").collect::<Vec<_>>();
+        assert_eq!(set.len(), 2);
+        for item in set {
+            let expected = match item["inputs"].as_array().unwrap().len() {
+                1 => "struct Output",
+                _ => "struct Range",
+            };
+            assert_eq!(item["inputs"][0]["internalType"], expected);
+        }
+    }
+
+    #[test]
+    fn struct_names_survive_converting_back() {
+        let abi = serde_json::from_value::<RawAbi>(solidity_to_abi(ORACLE).unwrap()).unwrap();
+        let solidity = abi_to_solidity(abi, "Oracle").unwrap();
+        // Struct names come back from internalType, outputs included
+        assert!(solidity.contains("function get(Range calldata range) external view returns (Output[] memory outputs, uint256 count);"), "{}", solidity);
+        assert!(solidity.contains("function set(Range calldata value, uint256 id) external;"), "{}", solidity);
+    }
+}
@@ -0,0 +1,685 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand};
+use ethers::abi::RawAbi;
+use serde_json::{json, Value};
+use ::txn_decoder::abi_converter::{abi_to_solidity, solidity_to_abi};
+use ::txn_decoder::decoder::function_decoder::{self, Confidence, DecodedCall, DecodedParam, DecodedReturn};
+use ::txn_decoder::decoder::abi_guesser;
+use ::txn_decoder::decoder::calldata_encoder;
+use ::txn_decoder::decoder::call_tree::CallTree;
+use ::txn_decoder::decoder::event_decoder::{self, DecodedLog, EventDecoder};
+use ::txn_decoder::decoder::function_decoder_with_abi;
+use ::txn_decoder::decoder::revert_decoder::{self, DecodedRevert, RevertKind};
+use ::txn_decoder::decoder::signature_db::SignatureLookup;
+use ::txn_decoder::decoder::signature_parser;
+use ::txn_decoder::decoder::strict_decoder::EncodingViolation;
+use ::txn_decoder::decoder::txn_decoder::{self, Transaction};
+use ::txn_decoder::decoder::utils::{self, labeled_token_to_json};
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+// Every INPUT can be given inline, as a path to a file, or omitted / "-" to read stdin
+#[derive(Parser, Debug)]
+#[command(name = "txn-cli", about = "Decode Ethereum transactions and calldata, and convert between ABI and Solidity")]
+struct Cli {
+    /// Print JSON instead of human-readable output
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Extra signature database (text or JSON dump of selectors), consulted before 4byte.directory
+    #[arg(long = "signatures", value_name = "FILE", global = true)]
+    signatures: Vec<PathBuf>,
+
+    /// Only use local signature databases for selector lookups
+    #[arg(long, global = true)]
+    offline: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decode a raw signed transaction
+    DecodeTx {
+        input: Option<String>,
+        /// Also decode the transaction input against this ABI file
+        #[arg(long)]
+        abi: Option<String>,
+        /// Also decode the transaction input by looking up its selector
+        #[arg(long, conflicts_with = "abi")]
+        lookup: bool,
+    },
+    /// Decode calldata, against an ABI file when given or by selector lookup otherwise
+    DecodeCalldata {
+        input: Option<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        /// Show every candidate signature that matches the calldata, not just the best one
+        #[arg(long, conflicts_with = "abi")]
+        all: bool,
+        /// Also decode calls nested in the arguments (multicall, Safe transactions, universal router commands)
+        #[arg(long, conflicts_with_all = ["all", "guess"])]
+        nested: bool,
+        /// Guess the parameter types from the calldata when no signature matches
+        #[arg(long, conflicts_with = "abi")]
+        guess: bool,
+        /// Also decode calldata that is not canonically encoded, and report every encoding violation
+        /// (dirty padding, overlapping or out-of-order offsets, trailing bytes)
+        #[arg(long, conflicts_with_all = ["nested", "guess"])]
+        strict: bool,
+    },
+    /// Decode an event log, against an ABI file when given or by looking up topic0 otherwise. INPUT is
+    /// a log object with "topics" and "data", as returned by eth_getLogs, unless --topic / --data are given.
+    DecodeLog {
+        input: Option<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        /// Show every candidate signature and indexed layout that matches the log, not just the best one
+        #[arg(long, conflicts_with = "abi")]
+        all: bool,
+        /// Log topic, in order starting with topic0
+        #[arg(long = "topic", value_name = "TOPIC")]
+        topics: Vec<String>,
+        #[arg(long, default_value = "0x")]
+        data: String,
+    },
+    /// Decode revert data: Error(string), Panic(uint256), or a custom error from the ABI file or by selector lookup
+    DecodeRevert {
+        input: Option<String>,
+        #[arg(long)]
+        abi: Option<String>,
+    },
+    /// Decode the return data of a call, against a function in an ABI file or a signature with a return type
+    DecodeOutput {
+        input: Option<String>,
+        #[arg(long, requires = "function")]
+        abi: Option<String>,
+        /// Function name, full signature when it is overloaded, or 0x selector
+        #[arg(long, requires = "abi")]
+        function: Option<String>,
+        /// e.g. "balanceOf(address) -> uint256" or "getReserves() -> (uint112,uint112,uint32)"
+        #[arg(long, conflicts_with = "abi", required_unless_present = "abi")]
+        signature: Option<String>,
+    },
+    /// Encode calldata from a signature, or a function in an ABI file, and its arguments
+    EncodeCalldata {
+        /// e.g. "transfer(address to, uint256 amount)", or with --abi a function name, full signature or 0x selector
+        function: String,
+        /// Arguments in order. Arrays and tuples are given as JSON, e.g. '[1,2]' or '{"to":"0x...","value":1}'
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+        #[arg(long)]
+        abi: Option<String>,
+        /// All arguments as one JSON array instead, e.g. '["0x...", 100]', or a file holding it
+        #[arg(long = "args-json", value_name = "JSON", conflicts_with = "args")]
+        args_json: Option<String>,
+    },
+    /// Compute the selector of a signature, or look up the signatures of a 0x-prefixed selector
+    Selector {
+        input: Option<String>,
+    },
+    /// Generate a Solidity interface from an ABI
+    AbiToSol {
+        input: Option<String>,
+        /// Interface name
+        #[arg(long, default_value = "IContract")]
+        name: String,
+    },
+    /// Generate an ABI from Solidity source
+    SolToAbi {
+        input: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> CliResult<()> {
+    let signature_lookup = SignatureLookup::from_env_with(&cli.signatures, cli.offline)?;
+    match cli.command {
+        Command::DecodeTx { input, abi, lookup } => {
+            let tx = txn_decoder::decode_transaction(read_input(input.as_deref())?.trim())?;
+            let calldata = format!("0x{}", hex::encode(&tx.data));
+            let call = match abi {
+                Some(path) => Some((decode_with_abi(&path, &calldata)?, None)),
+                None if lookup => decode_with_lookup(&calldata, &signature_lookup).await?.into_iter().next(),
+                None => None,
+            };
+
+            if cli.json {
+                let mut output = serde_json::to_value(&tx)?;
+                if let Some((call, confidence)) = &call {
+                    output["decoded_input"] = call_to_json(call, *confidence);
+                }
+                print_json(&output)?;
+            } else {
+                print_transaction(&tx);
+                if let Some((call, confidence)) = &call {
+                    println!();
+                    print_call(call, *confidence, 0);
+                }
+            }
+        }
+        Command::DecodeCalldata { input, abi, all, strict: true, .. } => {
+            let calldata = read_input(input.as_deref())?;
+            let mut calls = match abi {
+                Some(path) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    let (call, violations) = function_decoder_with_abi::CalldataDecoder::new(&abi_json)?.decode_call_strict(calldata.trim())?;
+                    vec![(call, None, violations)]
+                }
+                None => function_decoder::decode_calldata_strict(calldata.trim(), &signature_lookup)
+                    .await?
+                    .into_iter()
+                    .map(|(candidate, violations)| (candidate.call, Some(candidate.confidence), violations))
+                    .collect(),
+            };
+            if !all {
+                calls.truncate(1);
+            }
+
+            let to_json = |(call, confidence, violations): &(DecodedCall, Option<Confidence>, Vec<EncodingViolation>)| {
+                let mut output = call_to_json(call, *confidence);
+                output["violations"] = json!(violations);
+                output
+            };
+            if cli.json && all {
+                print_json(&Value::Array(calls.iter().map(to_json).collect()))?;
+            } else if cli.json {
+                print_json(&to_json(&calls[0]))?;
+            } else {
+                for (i, (call, confidence, violations)) in calls.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    print_call(call, *confidence, 0);
+                    match violations.len() {
+                        0 => println!("Encoding: canonical"),
+                        count => {
+                            println!("Encoding: {} violation{}", count, if count == 1 { "" } else { "s" });
+                            violations.iter().for_each(|violation| println!("  {}", violation));
+                        }
+                    }
+                }
+            }
+        }
+        Command::DecodeCalldata { input, abi, nested: true, .. } => {
+            let calldata = read_input(input.as_deref())?;
+            let tree = match abi {
+                Some(path) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    function_decoder_with_abi::CalldataDecoder::new(&abi_json)?
+                        .decode_call_tree(calldata.trim(), Some(&signature_lookup))
+                        .await?
+                }
+                None => function_decoder::decode_call_tree(calldata.trim(), &signature_lookup).await?,
+            };
+
+            if cli.json {
+                print_json(&call_tree_to_json(&tree))?;
+            } else {
+                print_call_tree(&tree, 0);
+            }
+        }
+        Command::DecodeCalldata { input, abi, all, nested: false, guess, strict: false } => {
+            let calldata = read_input(input.as_deref())?;
+            let mut calls = match abi {
+                Some(path) => vec![(decode_with_abi(&path, calldata.trim())?, None)],
+                None => match decode_with_lookup(calldata.trim(), &signature_lookup).await {
+                    Err(e) if guess => {
+                        eprintln!("{}, guessing the parameter types", e);
+                        let guessed = abi_guesser::guess_calldata(calldata.trim())?;
+                        vec![(guessed.call, Some(guessed.confidence))]
+                    }
+                    result => result?,
+                },
+            };
+            if !all {
+                calls.truncate(1);
+            }
+
+            if cli.json && all {
+                print_json(&Value::Array(calls.iter().map(|(call, confidence)| call_to_json(call, *confidence)).collect()))?;
+            } else if cli.json {
+                print_json(&call_to_json(&calls[0].0, calls[0].1))?;
+            } else {
+                for (i, (call, confidence)) in calls.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    print_call(call, *confidence, 0);
+                }
+            }
+        }
+        Command::DecodeLog { input, abi, all, topics, data } => {
+            let (topics, data) = if topics.is_empty() && data == "0x" {
+                let log: Value = serde_json::from_str(&read_input(input.as_deref())?)
+                    .map_err(|e| format!("Invalid log JSON: {}", e))?;
+                let topics = serde_json::from_value::<Vec<String>>(log["topics"].clone())
+                    .map_err(|e| format!("Invalid log topics: {}", e))?;
+                (topics, log["data"].as_str().unwrap_or("0x").to_string())
+            } else {
+                (topics, data)
+            };
+
+            let (topics, data) = event_decoder::parse_log_hex(&topics, &data)?;
+            let mut logs = match abi {
+                Some(path) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    vec![(EventDecoder::new(&abi_json)?.decode_log(&topics, &data)?, None)]
+                }
+                None => event_decoder::decode_log_candidates(&topics, &data, &signature_lookup)
+                    .await?
+                    .into_iter()
+                    .map(|candidate| (candidate.log, Some(candidate.confidence)))
+                    .collect(),
+            };
+            if !all {
+                logs.truncate(1);
+            }
+
+            if cli.json && all {
+                print_json(&Value::Array(logs.iter().map(|(log, confidence)| log_to_json(log, *confidence)).collect()))?;
+            } else if cli.json {
+                print_json(&log_to_json(&logs[0].0, logs[0].1))?;
+            } else {
+                for (i, (log, confidence)) in logs.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    print_log(log, *confidence);
+                }
+            }
+        }
+        Command::DecodeRevert { input, abi } => {
+            let revert_data = read_input(input.as_deref())?;
+            let revert = match abi {
+                Some(path) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    function_decoder_with_abi::CalldataDecoder::new(&abi_json)?.decode_revert(&revert_data)?
+                }
+                None => revert_decoder::decode_revert(&revert_data, &signature_lookup).await?,
+            };
+
+            if cli.json {
+                print_json(&revert_to_json(&revert))?;
+            } else {
+                print_revert(&revert);
+            }
+        }
+        Command::DecodeOutput { input, abi, function, signature } => {
+            let return_data = read_input(input.as_deref())?;
+            let output = match (abi, function, signature) {
+                (Some(path), Some(function), _) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    function_decoder_with_abi::CalldataDecoder::new(&abi_json)?.decode_output(&function, &return_data)?
+                }
+                (_, _, Some(signature)) => function_decoder::CalldataDecoder::new(&signature)?.decode_output(&return_data)?,
+                _ => unreachable!("enforced by clap"),
+            };
+
+            if cli.json {
+                print_json(&output_to_json(&output))?;
+            } else {
+                println!("Function: {}", output.signature);
+                print_params(&output.values, 1);
+            }
+        }
+        Command::EncodeCalldata { function, args, abi, args_json } => {
+            let args = match args_json {
+                Some(json) => serde_json::from_str::<Vec<Value>>(&read_input(Some(&json))?)
+                    .map_err(|e| format!("--args-json must be a JSON array: {}", e))?,
+                None => args.into_iter().map(Value::String).collect(),
+            };
+            let (signature, calldata) = match abi {
+                Some(path) => {
+                    let abi_json = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                    function_decoder_with_abi::CalldataDecoder::new(&abi_json)?.encode_call(&function, &args)?
+                }
+                None => {
+                    let signature = signature_parser::parse_signature(&function)?;
+                    (signature.canonical(), calldata_encoder::encode_call(&signature, &args)?)
+                }
+            };
+
+            let calldata = format!("0x{}", hex::encode(calldata));
+            if cli.json {
+                print_json(&json!({ "signature": signature, "selector": &calldata[..10], "calldata": calldata }))?;
+            } else {
+                println!("{}", calldata);
+            }
+        }
+        Command::Selector { input } => {
+            let input = read_input(input.as_deref())?;
+            let input = input.trim();
+            if is_selector(input) {
+                let signatures = signature_lookup.lookup(input).await?;
+                if cli.json {
+                    print_json(&json!({ "selector": input, "signatures": signatures }))?;
+                } else {
+                    signatures.iter().for_each(|signature| println!("{}", signature));
+                }
+            } else {
+                let (signature, selector) = selector_for(input)?;
+                if cli.json {
+                    print_json(&json!({ "signature": signature, "selector": selector }))?;
+                } else {
+                    println!("{}  {}", selector, signature);
+                }
+            }
+        }
+        Command::AbiToSol { input, name } => {
+            let abi = serde_json::from_str::<RawAbi>(&read_input(input.as_deref())?)
+                .map_err(|e| format!("Invalid ABI: {}", e))?;
+            let solidity = abi_to_solidity(abi, &name)?;
+            if cli.json {
+                print_json(&json!({ "solidity": solidity }))?;
+            } else {
+                print!("{}", solidity);
+            }
+        }
+        Command::SolToAbi { input } => {
+            // The ABI is JSON either way
+            print_json(&solidity_to_abi(&read_input(input.as_deref())?)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_input(input: Option<&str>) -> CliResult<String> {
+    match input {
+        None | Some("-") => {
+            let mut buffer = String::new();
+            std::io::stdin().read_to_string(&mut buffer)?;
+            Ok(buffer)
+        }
+        Some(value) if Path::new(value).is_file() => Ok(std::fs::read_to_string(value)?),
+        Some(value) => Ok(value.to_string()),
+    }
+}
+
+fn decode_with_abi(abi_path: &str, calldata: &str) -> CliResult<DecodedCall> {
+    let abi_json = std::fs::read_to_string(abi_path).map_err(|e| format!("Failed to read {}: {}", abi_path, e))?;
+    function_decoder_with_abi::CalldataDecoder::new(&abi_json)?.decode_call(calldata)
+}
+
+// Candidates best first; ABI decoding is exact so only looked-up calls carry a confidence
+async fn decode_with_lookup(calldata: &str, lookup: &SignatureLookup) -> CliResult<Vec<(DecodedCall, Option<Confidence>)>> {
+    Ok(function_decoder::decode_calldata_candidates(calldata, lookup)
+        .await?
+        .into_iter()
+        .map(|candidate| (candidate.call, Some(candidate.confidence)))
+        .collect())
+}
+
+fn is_selector(input: &str) -> bool {
+    let digits = utils::remove_0x_prefix(input);
+    input.len() != digits.len() && digits.len() == 8 && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Accepts "transfer(address,uint256)" as well as declarations with names, e.g. "function transfer(address to, uint256 amount)".
+// Events hash to their full topic rather than a 4-byte selector.
+fn selector_for(input: &str) -> CliResult<(String, String)> {
+    let signature = signature_parser::parse_signature(input)?.canonical();
+    let hash = utils::keccak256(signature.as_bytes());
+    if input.trim_start().starts_with("event ") {
+        return Ok((signature, format!("0x{}", hex::encode(hash))));
+    }
+    Ok((signature, format!("0x{}", hex::encode(&hash[..4]))))
+}
+
+fn print_json(value: &Value) -> CliResult<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn call_to_json(call: &DecodedCall, confidence: Option<Confidence>) -> Value {
+    let mut output = json!({
+        "function_name": call.function_name,
+        "signature": call.signature,
+        "arguments": params_to_json(&call.params),
+    });
+    if let Some(confidence) = confidence {
+        output["confidence"] = json!(confidence);
+    }
+    output
+}
+
+fn output_to_json(output: &DecodedReturn) -> Value {
+    json!({
+        "function_name": output.function_name,
+        "signature": output.signature,
+        "outputs": params_to_json(&output.values),
+    })
+}
+
+fn params_to_json(params: &[DecodedParam]) -> Vec<Value> {
+    params
+        .iter()
+        .map(|param| json!({
+            "name": param.name,
+            "type": param.type_name(),
+            "internal_type": param.internal_type,
+            "value": labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &param.components),
+        }))
+        .collect()
+}
+
+fn call_tree_to_json(tree: &CallTree) -> Value {
+    let mut output = call_to_json(&tree.call, tree.confidence);
+    output["inner_calls"] = tree
+        .inner
+        .iter()
+        .map(|inner| json!({
+            "path": inner.path,
+            "to": inner.to.map(|to| format!("0x{:x}", to)),
+            "value": inner.value.map(|value| value.to_string()),
+            "delegate_call": inner.delegate_call,
+            "data": format!("0x{}", hex::encode(&inner.data)),
+            "call": inner.tree.as_ref().map(call_tree_to_json),
+        }))
+        .collect();
+    output
+}
+
+fn print_call_tree(tree: &CallTree, indent: usize) {
+    print_call(&tree.call, tree.confidence, indent);
+    for inner in &tree.inner {
+        let mut target = String::new();
+        if let Some(to) = inner.to {
+            target.push_str(&format!(" to 0x{:x}", to));
+        }
+        if let Some(value) = inner.value.filter(|value| !value.is_zero()) {
+            target.push_str(&format!(" value {}", value));
+        }
+        if inner.delegate_call {
+            target.push_str(" (delegatecall)");
+        }
+        println!("{}  -> {}{}", "  ".repeat(indent), inner.path, target);
+        match &inner.tree {
+            Some(tree) => print_call_tree(tree, indent + 2),
+            None if inner.data.is_empty() => {}
+            None => println!("{}    data: 0x{}", "  ".repeat(indent), hex::encode(&inner.data)),
+        }
+    }
+}
+
+fn print_call(call: &DecodedCall, confidence: Option<Confidence>, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match confidence {
+        Some(confidence) => println!("{}Function: {} ({:?} confidence)", pad, call.signature, confidence),
+        None => println!("{}Function: {}", pad, call.signature),
+    }
+    print_params(&call.params, indent + 1);
+}
+
+fn print_params(params: &[DecodedParam], indent: usize) {
+    for (i, param) in params.iter().enumerate() {
+        let name = param.name.clone().unwrap_or_else(|| format!("[{}]", i));
+        let lines = utils::format_labeled_param(
+            &name,
+            &param.kind,
+            param.internal_type.as_deref(),
+            param.abi_type.as_deref(),
+            &param.value,
+            &param.components,
+            indent,
+        );
+        lines.iter().for_each(|line| println!("{}", line));
+    }
+}
+
+fn log_to_json(log: &DecodedLog, confidence: Option<Confidence>) -> Value {
+    let mut output = json!({
+        "event_name": log.event_name,
+        "signature": log.signature,
+        "anonymous": log.anonymous,
+        "arguments": log.params.iter().map(|param| json!({
+            "name": param.name,
+            "type": param.type_name(),
+            "indexed": param.indexed,
+            "hashed": param.hashed,
+            "value": labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &[]),
+        })).collect::<Vec<_>>(),
+    });
+    if let Some(confidence) = confidence {
+        output["confidence"] = json!(confidence);
+    }
+    output
+}
+
+fn print_log(log: &DecodedLog, confidence: Option<Confidence>) {
+    let note = match confidence {
+        Some(confidence) => format!(" ({:?} confidence)", confidence),
+        None if log.anonymous => " (anonymous)".to_string(),
+        None => String::new(),
+    };
+    println!("Event: {}{}", log.signature, note);
+    for (i, param) in log.params.iter().enumerate() {
+        let name = param.name.clone().unwrap_or_else(|| format!("[{}]", i));
+        let value = match labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &[]) {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        let note = match (param.indexed, param.hashed) {
+            (true, true) => " indexed, hash",
+            (true, false) => " indexed",
+            _ => "",
+        };
+        println!("  {} ({}{}): {}", name, param.type_name(), note, value);
+    }
+}
+
+fn revert_to_json(revert: &DecodedRevert) -> Value {
+    let mut output = json!({
+        "kind": revert.kind,
+        "name": revert.name,
+        "signature": revert.signature,
+        "reason": revert.reason,
+        "arguments": params_to_json(&revert.params),
+    });
+    if let Some(confidence) = revert.confidence {
+        output["confidence"] = json!(confidence);
+    }
+    output
+}
+
+fn print_revert(revert: &DecodedRevert) {
+    match revert.kind {
+        RevertKind::Empty => println!("Reverted without data"),
+        RevertKind::Error => println!("Error: {}", revert.reason.as_deref().unwrap_or_default()),
+        RevertKind::Panic => {
+            let code = revert.params[0].value.clone().into_uint().unwrap_or_default();
+            println!("Panic: {} (code 0x{:02x})", revert.reason.as_deref().unwrap_or_default(), code);
+        }
+        RevertKind::Custom => {
+            match revert.confidence {
+                Some(confidence) => println!("Custom error: {} ({:?} confidence)", revert.signature, confidence),
+                None => println!("Custom error: {}", revert.signature),
+            }
+            print_params(&revert.params, 1);
+        }
+    }
+}
+
+fn print_transaction(tx: &Transaction) {
+    let field = |name: &str, value: String| println!("  {:<26}{}", format!("{}:", name), value);
+
+    println!("Transaction ({:?})", tx.transaction_type);
+    field("Hash", format!("0x{:x}", tx.hash));
+    if let Some(from) = tx.from {
+        field("From", format!("0x{:x}", from));
+    }
+    field("To", tx.to.map_or("(contract creation)".to_string(), |to| format!("0x{:x}", to)));
+    if let Some(chain_id) = tx.chain_id {
+        field("Chain ID", chain_id.to_string());
+    }
+    if let Some(nonce) = tx.nonce {
+        field("Nonce", nonce.to_string());
+    }
+    field("Value", tx.value.to_string());
+    field("Gas Limit", tx.gas_limit.to_string());
+    if let Some(gas_price) = tx.gas_price {
+        field("Gas Price", gas_price.to_string());
+    }
+    if let Some(fee) = tx.max_priority_fee_per_gas {
+        field("Max Priority Fee Per Gas", fee.to_string());
+    }
+    if let Some(fee) = tx.max_fee_per_gas {
+        field("Max Fee Per Gas", fee.to_string());
+    }
+    if let Some(fee) = tx.max_fee_per_blob_gas {
+        field("Max Fee Per Blob Gas", fee.to_string());
+    }
+    if let Some(access_list) = &tx.access_list {
+        field("Access List", format!("{} entries", access_list.0.len()));
+        for item in &access_list.0 {
+            println!("    0x{:x}", item.0);
+            for key in &item.1 {
+                println!("      0x{:x}", key);
+            }
+        }
+    }
+    if let Some(hashes) = &tx.blob_versioned_hashes {
+        field("Blob Versioned Hashes", format!("{} blobs", hashes.len()));
+        for hash in hashes {
+            println!("    0x{:x}", hash);
+        }
+    }
+    if let Some(sidecar) = &tx.blob_sidecar {
+        field("Blob Sidecar", format!("{} blobs, commitments and proofs", sidecar.blobs.len()));
+    }
+    if let Some(authorizations) = &tx.authorization_list {
+        field("Authorization List", format!("{} entries", authorizations.0.len()));
+        for item in &authorizations.0 {
+            let authority = item.authority.map_or("unrecoverable".to_string(), |a| format!("0x{:x}", a));
+            println!("    {} -> 0x{:x} (chain {}, nonce {})", authority, item.address, item.chain_id, item.nonce);
+        }
+    }
+    if let Some(source_hash) = tx.source_hash {
+        field("Source Hash", format!("0x{:x}", source_hash));
+    }
+    if let Some(mint) = tx.mint {
+        field("Mint", mint.to_string());
+    }
+    if let Some(is_system_tx) = tx.is_system_tx {
+        field("System Transaction", is_system_tx.to_string());
+    }
+    field("Data", format!("0x{} ({} bytes)", hex::encode(&tx.data), tx.data.len()));
+    if let (Some(v), Some(r), Some(s)) = (tx.v, tx.r, tx.s) {
+        field("V", v.to_string());
+        field("R", format!("0x{:064x}", r));
+        field("S", format!("0x{:064x}", s));
+    }
+    if let Some(signing_hash) = tx.signing_hash {
+        field("Signing Hash", format!("0x{:x}", signing_hash));
+    }
+}
@@ -0,0 +1,302 @@
+use anyhow::Error;
+use ethabi::ParamType;
+use ethereum_types::U256;
+use super::function_decoder::{Confidence, DecodedCall, DecodedParam, RankedCall};
+use super::utils;
+
+// Arrays of arrays of tuples of ... are rare, and every level multiplies the layouts to try
+const MAX_DEPTH: usize = 4;
+
+// Words looked at over all the layouts tried. Calldata full of words that could be offsets makes the
+// search blow up, so it gives up rather than hang.
+const WORK_BUDGET: usize = 1_000_000;
+
+// Proposes a parameter layout for calldata whose selector is unknown. The layout is always one that
+// re-encodes to the exact calldata, falling back to one static word per param, but the types are only
+// a guess: a uint256 could as well be a bool or an enum, and a bytes32 any fixed-size value.
+pub fn guess_calldata(call_data: &str) -> Result<RankedCall, Error> {
+    let calldata = hex::decode(utils::remove_0x_prefix(call_data.trim()))?;
+    if calldata.len() < 4 {
+        return Err(Error::msg("Call data is too short"));
+    }
+    if !(calldata.len() - 4).is_multiple_of(32) {
+        return Err(Error::msg(format!(
+            "Cannot guess a layout, the arguments are {} bytes which is not a whole number of words",
+            calldata.len() - 4
+        )));
+    }
+
+    let selector = format!("0x{}", hex::encode(&calldata[..4]));
+    let types = guess_param_types(&calldata[4..])?;
+    let values = ethabi::decode(&types, &calldata[4..])?;
+    let signature = format!("{}({})", selector, types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(","));
+
+    Ok(RankedCall {
+        call: DecodedCall {
+            function_name: selector,
+            signature,
+            params: types
+                .into_iter()
+                .zip(values)
+                .map(|(kind, value)| DecodedParam { name: None, kind, internal_type: None, abi_type: None, value, components: Vec::new() })
+                .collect(),
+        },
+        confidence: Confidence::Low,
+    })
+}
+
+// `data` is everything after the selector. Fails when the search runs out of its work budget.
+pub fn guess_param_types(data: &[u8]) -> Result<Vec<ParamType>, Error> {
+    let mut guesser = Guesser { work_left: WORK_BUDGET };
+    let guessed = guesser.tuple(data, None, 0);
+    if guesser.work_left == 0 {
+        return Err(Error::msg(format!(
+            "Could not guess a layout for {} words of arguments, too many of them look like offsets",
+            data.len() / 32
+        )));
+    }
+    Ok(guessed
+        .filter(|types| reencodes_exactly(types, data))
+        .unwrap_or_else(|| data.chunks(32).map(guess_word).collect()))
+}
+
+fn reencodes_exactly(types: &[ParamType], data: &[u8]) -> bool {
+    ethabi::decode(types, data).map(|tokens| ethabi::encode(&tokens) == data).unwrap_or(false)
+}
+
+struct Guesser {
+    work_left: usize,
+}
+
+impl Guesser {
+    // False once the budget is spent, after which every guess fails
+    fn spend(&mut self, words: usize) -> bool {
+        self.work_left = self.work_left.saturating_sub(words.max(1));
+        self.work_left > 0
+    }
+
+    // Guesses the types of an encoded tuple: a head of one word per member, static values inline and
+    // dynamic ones as offsets into the tail. `count` is the number of members when known, as for array
+    // elements. Offsets solc produces point past the head, in increasing order, and the first one marks
+    // where the head ends.
+    fn tuple(&mut self, data: &[u8], count: Option<usize>, depth: usize) -> Option<Vec<ParamType>> {
+        if depth > MAX_DEPTH || !data.len().is_multiple_of(32) {
+            return None;
+        }
+        let words = data.chunks(32).map(U256::from_big_endian).collect::<Vec<_>>();
+        if count.is_some_and(|count| count > words.len()) {
+            return None;
+        }
+
+        // Words that could be offsets, rejected one at a time when what they point at makes no sense
+        let candidates = words
+            .iter()
+            .enumerate()
+            .filter(|(i, word)| {
+                **word < U256::from(data.len()) && word.low_u64().is_multiple_of(32) && word.low_u64() as usize > i * 32
+            })
+            .map(|(i, word)| (i, word.low_u64() as usize))
+            .collect::<Vec<_>>();
+        let mut rejected = Vec::new();
+
+        loop {
+            if !self.spend(words.len()) {
+                return None;
+            }
+            let live = candidates.iter().filter(|candidate| !rejected.contains(*candidate)).collect::<Vec<_>>();
+            let head_len = match count {
+                Some(count) => count,
+                None => live.first().map_or(words.len(), |(_, offset)| offset / 32),
+            };
+            let offsets = live
+                .into_iter()
+                .filter(|(i, offset)| *i < head_len && *offset >= head_len * 32)
+                .copied()
+                .collect::<Vec<_>>();
+
+            match self.with_offsets(data, &words, head_len, &offsets, depth) {
+                Ok(types) => return Some(types),
+                Err(bad) if bad < offsets.len() => rejected.push(offsets[bad]),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    // Err holds the index (into `offsets`) of the offset that did not work out
+    fn with_offsets(&mut self, data: &[u8], words: &[U256], head_len: usize, offsets: &[(usize, usize)], depth: usize) -> Result<Vec<ParamType>, usize> {
+        let increasing = offsets.windows(2).position(|pair| pair[1].1 <= pair[0].1);
+        if let Some(bad) = increasing {
+            return Err(bad + 1);
+        }
+        // The tail starts right after the head, unless there is nothing dynamic at all
+        match offsets.first() {
+            Some((_, first)) if *first != head_len * 32 => return Err(0),
+            None if head_len != words.len() => return Err(0),
+            _ => {}
+        }
+
+        let mut types = words[..head_len].iter().map(|word| guess_word(&word_bytes(*word))).collect::<Vec<_>>();
+        for (n, (i, offset)) in offsets.iter().enumerate() {
+            let end = offsets.get(n + 1).map_or(data.len(), |(_, next)| *next);
+            types[*i] = self.dynamic(&data[*offset..end], depth).ok_or(n)?;
+        }
+        Ok(types)
+    }
+
+    // A dynamic value: bytes or string, an array, or a tuple with dynamic members. Arrays of dynamic
+    // elements and tuples both need offsets that line up exactly, so they are tried before arrays of
+    // static elements, which only need the content to split evenly: (uint256,string) with a small
+    // number would otherwise read as that many static elements.
+    fn dynamic(&mut self, region: &[u8], depth: usize) -> Option<ParamType> {
+        if region.len() < 32 || depth > MAX_DEPTH {
+            return None;
+        }
+        let length = U256::from_big_endian(&region[..32]);
+        let content = &region[32..];
+        let length = (length <= U256::from(content.len())).then(|| length.as_usize());
+
+        // bytes / string: the content padded with zeros to whole words
+        if let Some(length) = length {
+            if length.div_ceil(32) * 32 == content.len() && content[length..].iter().all(|b| *b == 0) {
+                return Some(match std::str::from_utf8(&content[..length]) {
+                    Ok(s) if !s.is_empty() && s.chars().all(|c| !c.is_control() || c.is_whitespace()) => ParamType::String,
+                    _ => ParamType::Bytes,
+                });
+            }
+        }
+
+        let length = length.filter(|length| *length > 0);
+        if let Some(element) = length.and_then(|length| self.dynamic_elements(content, length, depth)) {
+            return Some(ParamType::Array(Box::new(element)));
+        }
+        // A tuple with dynamic members is encoded in place, without a length
+        if let Some(types) = self.tuple(region, None, depth + 1).filter(|types| types.iter().any(is_dynamic)) {
+            return Some(ParamType::Tuple(types));
+        }
+        length
+            .and_then(|length| static_elements(content, length))
+            .map(|element| ParamType::Array(Box::new(element)))
+    }
+
+    // Dynamic array elements, each behind an offset relative to the start of the elements
+    fn dynamic_elements(&mut self, content: &[u8], length: usize, depth: usize) -> Option<ParamType> {
+        self.tuple(content, Some(length), depth + 1)
+            .filter(|types| types.iter().all(is_dynamic))
+            .and_then(|types| unify(types.into_iter()))
+    }
+}
+
+// Static array elements of one or more words each
+fn static_elements(content: &[u8], length: usize) -> Option<ParamType> {
+    let words_per_element = content.len() / 32 / length;
+    if words_per_element == 0 || words_per_element * length * 32 != content.len() {
+        return None;
+    }
+    let elements = content.chunks(words_per_element * 32).collect::<Vec<_>>();
+    let mut columns = (0..words_per_element)
+        .map(|column| unify(elements.iter().map(|element| guess_word(&element[column * 32..(column + 1) * 32]))))
+        .collect::<Option<Vec<_>>>()?;
+    match columns.len() {
+        1 => columns.pop(),
+        _ => Some(ParamType::Tuple(columns)),
+    }
+}
+
+pub(crate) fn is_dynamic(kind: &ParamType) -> bool {
+    match kind {
+        ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+        ParamType::FixedArray(inner, _) => is_dynamic(inner),
+        ParamType::Tuple(kinds) => kinds.iter().any(is_dynamic),
+        _ => false,
+    }
+}
+
+// One type for values guessed separately, e.g. the elements of an array
+fn unify(mut kinds: impl Iterator<Item = ParamType>) -> Option<ParamType> {
+    let first = kinds.next()?;
+    kinds.try_fold(first, |unified, kind| match (unified, kind) {
+        (a, b) if a == b => Some(a),
+        (ParamType::FixedBytes(32), b) | (b, ParamType::FixedBytes(32)) if !is_dynamic(&b) => Some(ParamType::FixedBytes(32)),
+        (ParamType::Int(_), ParamType::Uint(_)) | (ParamType::Uint(_), ParamType::Int(_)) => Some(ParamType::Int(256)),
+        (ParamType::Address, ParamType::Uint(_)) | (ParamType::Uint(_), ParamType::Address) => Some(ParamType::Uint(256)),
+        (ParamType::Bytes, ParamType::String) | (ParamType::String, ParamType::Bytes) => Some(ParamType::Bytes),
+        _ => None,
+    })
+}
+
+// Static words: addresses have 12 leading zero bytes but are rarely small, negative ints are
+// sign-extended, and anything else that fills the high bytes is fixed bytes such as a hash or a bytes4
+fn guess_word(word: &[u8]) -> ParamType {
+    let leading_zeros = word.iter().take_while(|b| **b == 0).count();
+    let trailing_zeros = word.iter().rev().take_while(|b| **b == 0).count();
+    match leading_zeros {
+        32 => ParamType::Uint(256),
+        12..=13 => ParamType::Address,
+        0 if word.iter().take(8).all(|b| *b == 0xff) && trailing_zeros == 0 => ParamType::Int(256),
+        0..=11 => ParamType::FixedBytes(32),
+        _ => ParamType::Uint(256),
+    }
+}
+
+fn word_bytes(word: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::decoder::calldata_encoder::encode_calldata;
+    use crate::decoder::signature_parser::parse_signature;
+
+    // Encodes the args with the signature, then checks the guess gets the param types back
+    fn assert_round_trip(signature: &str, args: serde_json::Value) {
+        let calldata = encode_calldata(signature, args.as_array().unwrap()).unwrap();
+        let expected = parse_signature(signature).unwrap().input_types();
+        let guessed = guess_param_types(&calldata[4..]).unwrap();
+        assert_eq!(guessed, expected, "{}", signature);
+        assert!(guess_calldata(&format!("0x{}", hex::encode(&calldata))).is_ok());
+    }
+
+    #[test]
+    fn static_params() {
+        assert_round_trip("f(address,uint256)", json!(["0x8ba1f109551bd432803012645ac136ddd64dba72", 5]));
+        assert_round_trip("f(int256,bytes32)", json!([-42, format!("0x{}", "ab".repeat(32))]));
+    }
+
+    #[test]
+    fn dynamic_params() {
+        assert_round_trip("f(string,bytes)", json!(["hello", "0xff00ff"]));
+        assert_round_trip("f(string,uint256[],bytes)", json!(["name", [7, 8], "0xdeadbeef"]));
+        assert_round_trip("f(bytes[])", json!([["0x1234", "0xabcdef"]]));
+        assert_round_trip("f(string[])", json!([["hello", "world"]]));
+        assert_round_trip("f(uint256[][])", json!([[[1, 2], [3]]]));
+    }
+
+    #[test]
+    fn tuples() {
+        let a = "0x1111111111111111111111111111111111111111";
+        let b = "0x2222222222222222222222222222222222222222";
+        assert_round_trip("f((address,uint256)[])", json!([[[a, 1], [b, 2]]]));
+        assert_round_trip("f((address,bytes)[])", json!([[[a, "0x12345678"], [b, "0xabcd"]]]));
+        assert_round_trip("f(uint256,(address,string[]))", json!([9, [a, ["x", "yy"]]]));
+    }
+
+    // A small number ahead of a dynamic member also reads as the length of an array of static elements
+    #[test]
+    fn tuples_with_a_small_leading_number() {
+        assert_round_trip("f((uint256,bytes))", json!([[1, "0xdeadbeef"]]));
+        assert_round_trip("f((uint256,string)[])", json!([[[1, "hello"], [2, "world"]]]));
+        assert_round_trip("f((uint256,string)[])", json!([[[3, "a"], [4, "b"], [5, "c"]]]));
+        assert_round_trip("f((uint256,string)[],bytes[])", json!([[[1, "hello"], [2, "world"]], ["0x1234", "0xabcdef"]]));
+    }
+
+    #[test]
+    fn gives_up_when_every_word_looks_like_an_offset() {
+        let words = (1..=600).map(|i| format!("{:064x}", i * 32)).collect::<String>();
+        let error = guess_calldata(&format!("0xdeadbeef{}", words)).unwrap_err();
+        assert!(error.to_string().starts_with("Could not guess a layout"), "{}", error);
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use anyhow::Error;
+use ethabi::Token;
+use ethereum_types::{Address, U256};
+use futures::future::BoxFuture;
+use super::function_decoder::{self, Confidence, DecodedCall, DecodedParam, ParamLabel};
+use super::function_decoder_with_abi;
+use super::signature_db::SignatureLookup;
+use super::signature_parser;
+
+// Wrappers inside wrappers (a Safe calling a multicall calling a router...) stop being unwrapped past this
+const MAX_DEPTH: usize = 8;
+
+// Distinct selectors looked up for one tree. Lookups may go to 4byte one at a time, so a batch of many
+// different calls leaves the ones past this undecoded rather than take minutes.
+const MAX_LOOKUPS: usize = 16;
+
+// A decoded call together with the calls found inside its arguments
+#[derive(Debug, Clone)]
+pub struct CallTree {
+    pub call: DecodedCall,
+    // None when decoded against an ABI or a known payload format
+    pub confidence: Option<Confidence>,
+    pub inner: Vec<InnerCall>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InnerCall {
+    // Where the payload sits in the outer call, e.g. "data", "calls[1].callData" or "transactions[0]"
+    pub path: String,
+    // Target and value, when the wrapper says where the call goes
+    pub to: Option<Address>,
+    pub value: Option<U256>,
+    pub delegate_call: bool,
+    pub data: Vec<u8>,
+    // None when the payload could not be decoded
+    pub tree: Option<CallTree>,
+}
+
+// Where execTransaction-style wrappers keep the target, value and payload (and operation, for Safe)
+const KNOWN_WRAPPERS: &[(&str, usize, usize, usize, Option<usize>)] = &[
+    ("execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)", 0, 1, 2, Some(3)),
+    ("execTransactionFromModule(address,uint256,bytes,uint8)", 0, 1, 2, Some(3)),
+    ("execute(address,uint256,bytes)", 0, 1, 2, None),
+];
+
+// Input layouts of the Uniswap universal router commands, by command type
+const ROUTER_COMMANDS: &[(u8, &str)] = &[
+    (0x00, "V3_SWAP_EXACT_IN(address,uint256,uint256,bytes,bool)"),
+    (0x01, "V3_SWAP_EXACT_OUT(address,uint256,uint256,bytes,bool)"),
+    (0x02, "PERMIT2_TRANSFER_FROM(address,address,uint160)"),
+    (0x03, "PERMIT2_PERMIT_BATCH(((address,uint160,uint48,uint48)[],address,uint256),bytes)"),
+    (0x04, "SWEEP(address,address,uint256)"),
+    (0x05, "TRANSFER(address,address,uint256)"),
+    (0x06, "PAY_PORTION(address,address,uint256)"),
+    (0x08, "V2_SWAP_EXACT_IN(address,uint256,uint256,address[],bool)"),
+    (0x09, "V2_SWAP_EXACT_OUT(address,uint256,uint256,address[],bool)"),
+    (0x0a, "PERMIT2_PERMIT(((address,uint160,uint48,uint48),address,uint256),bytes)"),
+    (0x0b, "WRAP_ETH(address,uint256)"),
+    (0x0c, "UNWRAP_WETH(address,uint256)"),
+    (0x0d, "PERMIT2_TRANSFER_FROM_BATCH((address,address,uint160,address)[])"),
+    (0x0e, "BALANCE_CHECK_ERC20(address,address,uint256)"),
+];
+
+// Decodes calldata and every call nested in it. Each call is decoded against the ABI when it has the
+// selector, and by signature lookup otherwise.
+pub async fn decode_call_tree(
+    calldata: &[u8],
+    abi: Option<&function_decoder_with_abi::CalldataDecoder>,
+    lookup: Option<&SignatureLookup>,
+) -> Result<CallTree, Error> {
+    let lookup = lookup.map(|lookup| CachedLookup { lookup, cache: Mutex::new(HashMap::new()) });
+    decode_tree(calldata.to_vec(), abi, lookup.as_ref(), 0).await
+}
+
+// Payloads of a batch often repeat the same few selectors, each is only looked up once per tree
+struct CachedLookup<'a> {
+    lookup: &'a SignatureLookup,
+    // Failed lookups are kept too, as their error message
+    cache: Mutex<HashMap<String, Result<Vec<String>, String>>>,
+}
+
+impl CachedLookup<'_> {
+    async fn signatures(&self, selector: &str) -> Result<Vec<String>, Error> {
+        {
+            let cache = self.cache.lock().unwrap();
+            if let Some(cached) = cache.get(selector) {
+                return cached.clone().map_err(Error::msg);
+            }
+            if cache.len() >= MAX_LOOKUPS {
+                return Err(Error::msg(format!("Not looking up {}, the call tree already has {} different selectors", selector, MAX_LOOKUPS)));
+            }
+        }
+        let signatures = self.lookup.lookup(selector).await.map_err(|e| e.to_string());
+        self.cache.lock().unwrap().insert(selector.to_string(), signatures.clone());
+        signatures.map_err(Error::msg)
+    }
+}
+
+fn decode_tree<'a>(
+    calldata: Vec<u8>,
+    abi: Option<&'a function_decoder_with_abi::CalldataDecoder>,
+    lookup: Option<&'a CachedLookup<'a>>,
+    depth: usize,
+) -> BoxFuture<'a, Result<CallTree, Error>> {
+    Box::pin(async move {
+        let hex_data = format!("0x{}", hex::encode(&calldata));
+        let abi_call = abi.and_then(|abi| abi.decode_call(&hex_data).ok());
+        let (call, confidence) = match (abi_call, lookup) {
+            (Some(call), _) => (call, None),
+            (None, Some(_)) if calldata.len() < 4 => return Err(Error::msg("No valid function in calldata.")),
+            (None, Some(lookup)) => {
+                let signatures = lookup.signatures(&format!("0x{}", hex::encode(&calldata[..4]))).await?;
+                let best = function_decoder::rank_candidates(&hex_data, &calldata, &signatures)?.remove(0);
+                (best.call, Some(best.confidence))
+            }
+            (None, None) => return Err(Error::msg("Function not found in ABI")),
+        };
+
+        let mut inner = Vec::new();
+        if depth < MAX_DEPTH {
+            // Bytes that merely look like calldata are only worth showing when they decode, while the
+            // payloads of known wrappers are calls even when empty or unknown
+            let known = is_known_container(&call);
+            for mut inner_call in find_inner_calls(&call) {
+                if inner_call.tree.is_none() && looks_like_calldata(&inner_call.data) {
+                    inner_call.tree = decode_tree(inner_call.data.clone(), abi, lookup, depth + 1).await.ok();
+                }
+                if known || inner_call.tree.is_some() {
+                    inner.push(inner_call);
+                }
+            }
+        }
+
+        Ok(CallTree { call, confidence, inner })
+    })
+}
+
+fn is_known_container(call: &DecodedCall) -> bool {
+    call.signature == "multiSend(bytes)"
+        || is_router_execute(call)
+        || KNOWN_WRAPPERS.iter().any(|(signature, ..)| call.signature == *signature)
+}
+
+fn is_router_execute(call: &DecodedCall) -> bool {
+    call.signature == "execute(bytes,bytes[])" || call.signature == "execute(bytes,bytes[],uint256)"
+}
+
+fn find_inner_calls(call: &DecodedCall) -> Vec<InnerCall> {
+    let params = call.params.iter().map(|p| p.value.clone()).collect::<Vec<_>>();
+
+    if call.signature == "multiSend(bytes)" {
+        if let [Token::Bytes(transactions)] = params.as_slice() {
+            if let Some(calls) = parse_multi_send(transactions) {
+                return calls;
+            }
+        }
+    }
+
+    if is_router_execute(call) {
+        if let [Token::Bytes(commands), Token::Array(inputs), ..] = params.as_slice() {
+            return commands
+                .iter()
+                .zip(inputs)
+                .enumerate()
+                .map(|(i, (command, input))| {
+                    let data = input.clone().into_bytes().unwrap_or_default();
+                    InnerCall {
+                        path: format!("inputs[{}]", i),
+                        to: None,
+                        value: None,
+                        delegate_call: false,
+                        tree: decode_router_command(*command, &data),
+                        data,
+                    }
+                })
+                .collect();
+        }
+    }
+
+    if let Some((_, to, value, data, operation)) = KNOWN_WRAPPERS.iter().find(|(signature, ..)| call.signature == *signature) {
+        return vec![InnerCall {
+            path: call.params[*data].name.clone().unwrap_or_else(|| "data".to_string()),
+            to: params[*to].clone().into_address(),
+            value: params[*value].clone().into_uint(),
+            delegate_call: operation.and_then(|i| params[i].clone().into_uint()) == Some(U256::one()),
+            data: params[*data].clone().into_bytes().unwrap_or_default(),
+            tree: None,
+        }];
+    }
+
+    let mut inner = Vec::new();
+    for (i, param) in call.params.iter().enumerate() {
+        collect_bytes(&param.value, &param.components, param_name(param, i), None, &mut inner);
+    }
+    inner
+}
+
+fn param_name(param: &DecodedParam, index: usize) -> String {
+    param.name.clone().unwrap_or_else(|| format!("[{}]", index))
+}
+
+// Every bytes value that could be calldata, with the target address when it sits in a
+// (target, ..., callData) tuple as multicall contracts use
+fn collect_bytes(token: &Token, labels: &[ParamLabel], path: String, siblings: Option<&[Token]>, inner: &mut Vec<InnerCall>) {
+    match token {
+        Token::Bytes(data) if looks_like_calldata(data) => {
+            let siblings = siblings.unwrap_or_default();
+            let addresses = siblings.iter().filter_map(|t| t.clone().into_address()).collect::<Vec<_>>();
+            let values = siblings.iter().filter_map(|t| t.clone().into_uint()).collect::<Vec<_>>();
+            inner.push(InnerCall {
+                path,
+                to: addresses.first().copied(),
+                // Only unambiguous when the tuple has a single number, as in aggregate3Value
+                value: if values.len() == 1 { Some(values[0]) } else { None },
+                delegate_call: false,
+                data: data.clone(),
+                tree: None,
+            });
+        }
+        Token::Array(tokens) | Token::FixedArray(tokens) => {
+            for (i, token) in tokens.iter().enumerate() {
+                collect_bytes(token, labels, format!("{}[{}]", path, i), None, inner);
+            }
+        }
+        Token::Tuple(tokens) => {
+            for (i, token) in tokens.iter().enumerate() {
+                let label = labels.get(i).cloned().unwrap_or_default();
+                let name = label.name.unwrap_or_else(|| i.to_string());
+                collect_bytes(token, &label.components, format!("{}.{}", path, name), Some(tokens), inner);
+            }
+        }
+        _ => {}
+    }
+}
+
+// A selector followed by whole 32-byte words
+fn looks_like_calldata(data: &[u8]) -> bool {
+    data.len() >= 4 && (data.len() - 4).is_multiple_of(32)
+}
+
+// Safe's multiSend packs each transaction as operation (1 byte), to (20), value (32), data length (32), data
+fn parse_multi_send(mut transactions: &[u8]) -> Option<Vec<InnerCall>> {
+    let mut calls = Vec::new();
+    while !transactions.is_empty() {
+        if transactions.len() < 85 {
+            return None;
+        }
+        let length = U256::from_big_endian(&transactions[53..85]);
+        if length > U256::from(transactions.len() - 85) {
+            return None;
+        }
+        let end = 85 + length.as_usize();
+        calls.push(InnerCall {
+            path: format!("transactions[{}]", calls.len()),
+            to: Some(Address::from_slice(&transactions[1..21])),
+            value: Some(U256::from_big_endian(&transactions[21..53])),
+            delegate_call: transactions[0] == 1,
+            data: transactions[85..end].to_vec(),
+            tree: None,
+        });
+        transactions = &transactions[end..];
+    }
+    Some(calls)
+}
+
+// Router inputs are ABI encoded without a selector, their layout depends on the command byte
+fn decode_router_command(command: u8, input: &[u8]) -> Option<CallTree> {
+    // The high bit only marks the command as allowed to revert
+    let (_, signature) = ROUTER_COMMANDS.iter().find(|(kind, _)| *kind == command & 0x3f)?;
+    let types = signature_parser::parse_signature(signature).ok()?.input_types();
+    let tokens = ethabi::decode(&types, input).ok()?;
+
+    Some(CallTree {
+        call: DecodedCall {
+            function_name: signature.split('(').next().unwrap_or_default().to_string(),
+            signature: signature.to_string(),
+            params: types
+                .into_iter()
+                .zip(tokens)
+                .map(|(kind, value)| DecodedParam { name: None, kind, internal_type: None, abi_type: None, value, components: Vec::new() })
+                .collect(),
+        },
+        confidence: None,
+        inner: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use futures::future::FutureExt;
+    use serde_json::json;
+    use crate::decoder::calldata_encoder::encode_calldata;
+    use crate::decoder::signature_db::{LocalSignatureDb, SignatureSource};
+
+    // The bundled signatures, counting how often they are asked for
+    struct CountingSource(LocalSignatureDb, Arc<AtomicUsize>);
+
+    impl SignatureSource for CountingSource {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn lookup<'a>(&'a self, selector: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<String>>> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            futures::future::ready(Ok(self.0.get(selector).to_vec())).boxed()
+        }
+    }
+
+    fn counting_lookup() -> (SignatureLookup, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        (SignatureLookup::new().with_source(CountingSource(LocalSignatureDb::bundled(), count.clone())), count)
+    }
+
+    fn lookup() -> SignatureLookup {
+        SignatureLookup::new().with_source(LocalSignatureDb::bundled())
+    }
+
+    fn packed(operation: u8, to: Address, value: u64, data: &[u8]) -> Vec<u8> {
+        let mut tx = vec![operation];
+        tx.extend(to.as_bytes());
+        tx.extend(ethabi::encode(&[Token::Uint(value.into()), Token::Uint(data.len().into())]));
+        tx.extend(data);
+        tx
+    }
+
+    #[tokio::test]
+    async fn unpacks_multi_send() {
+        let token = Address::repeat_byte(0x11);
+        let module = Address::repeat_byte(0x22);
+        let transfer = encode_calldata("transfer(address,uint256)", &[json!(format!("{:?}", module)), json!("5")]).unwrap();
+        let transactions = [packed(0, token, 1, &transfer), packed(1, module, 0, &[])].concat();
+        let calldata = encode_calldata("multiSend(bytes)", &[json!(format!("0x{}", hex::encode(&transactions)))]).unwrap();
+
+        let tree = decode_call_tree(&calldata, None, Some(&lookup())).await.unwrap();
+        assert_eq!(tree.call.signature, "multiSend(bytes)");
+        assert_eq!(tree.inner.len(), 2);
+
+        let first = &tree.inner[0];
+        assert_eq!((first.path.as_str(), first.to, first.value, first.delegate_call), ("transactions[0]", Some(token), Some(1.into()), false));
+        let call = &first.tree.as_ref().unwrap().call;
+        assert_eq!(call.signature, "transfer(address,uint256)");
+        assert_eq!(call.params[0].value, Token::Address(module));
+
+        // Known payloads are kept even when there is nothing to decode
+        let second = &tree.inner[1];
+        assert_eq!((second.path.as_str(), second.to, second.delegate_call), ("transactions[1]", Some(module), true));
+        assert!(second.data.is_empty() && second.tree.is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_multi_send() {
+        let tx = packed(0, Address::zero(), 0, &[1, 2, 3, 4]);
+        assert!(parse_multi_send(&tx[..tx.len() - 1]).is_none());
+        assert!(parse_multi_send(&tx[..84]).is_none());
+        assert_eq!(parse_multi_send(&[]).unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn unpacks_router_commands() {
+        let recipient = Address::repeat_byte(0x33);
+        let wrap = ethabi::encode(&[Token::Address(recipient), Token::Uint(7.into())]);
+        let unwrap = ethabi::encode(&[Token::Address(recipient), Token::Uint(0.into())]);
+        let inputs = [&wrap, &unwrap, &vec![0xab]].map(|input| format!("0x{}", hex::encode(input)));
+        // WRAP_ETH, UNWRAP_WETH allowed to revert, and a command type that is not known
+        let calldata = encode_calldata(
+            "execute(bytes,bytes[],uint256)",
+            &[json!("0x0b8c3f"), json!(inputs), json!("1700000000")],
+        )
+        .unwrap();
+
+        let tree = decode_call_tree(&calldata, None, Some(&lookup())).await.unwrap();
+        assert_eq!(tree.inner.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(), ["inputs[0]", "inputs[1]", "inputs[2]"]);
+
+        let wrap_call = &tree.inner[0].tree.as_ref().unwrap().call;
+        assert_eq!(wrap_call.function_name, "WRAP_ETH");
+        assert_eq!(wrap_call.params[0].value, Token::Address(recipient));
+        assert_eq!(wrap_call.params[1].value, Token::Uint(7.into()));
+        assert_eq!(tree.inner[1].tree.as_ref().unwrap().call.function_name, "UNWRAP_WETH");
+        assert!(tree.inner[2].tree.is_none());
+        assert_eq!(tree.inner[2].data, [0xab]);
+    }
+
+    #[tokio::test]
+    async fn looks_each_selector_up_once() {
+        let token = Address::repeat_byte(0x11);
+        let transfer = |amount: u64| encode_calldata("transfer(address,uint256)", &[json!(format!("{:?}", token)), json!(amount.to_string())]).unwrap();
+        let unknown = [0xde, 0xad, 0xbe, 0xef];
+        let transactions = [
+            packed(0, token, 0, &transfer(1)),
+            packed(0, token, 0, &transfer(2)),
+            packed(0, token, 0, &unknown),
+            packed(0, token, 0, &unknown),
+            packed(0, token, 0, &transfer(3)),
+        ]
+        .concat();
+        let calldata = encode_calldata("multiSend(bytes)", &[json!(format!("0x{}", hex::encode(&transactions)))]).unwrap();
+
+        let (lookup, count) = counting_lookup();
+        let tree = decode_call_tree(&calldata, None, Some(&lookup)).await.unwrap();
+        let decoded = tree.inner.iter().map(|call| call.tree.is_some()).collect::<Vec<_>>();
+        assert_eq!(decoded, [true, true, false, false, true]);
+        // multiSend, transfer and the unknown selector
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn caps_the_lookups_per_tree() {
+        let transactions = (0..MAX_LOOKUPS as u32 + 4)
+            .flat_map(|i| packed(0, Address::zero(), 0, &i.to_be_bytes()))
+            .collect::<Vec<_>>();
+        let calldata = encode_calldata("multiSend(bytes)", &[json!(format!("0x{}", hex::encode(&transactions)))]).unwrap();
+
+        let (lookup, count) = counting_lookup();
+        let tree = decode_call_tree(&calldata, None, Some(&lookup)).await.unwrap();
+        assert_eq!(tree.inner.len(), MAX_LOOKUPS + 4);
+        assert_eq!(count.load(Ordering::SeqCst), MAX_LOOKUPS);
+    }
+}
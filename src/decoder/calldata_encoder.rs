@@ -0,0 +1,342 @@
+use std::fmt;
+use ethabi::{ParamType, Token};
+use ethereum_types::{Address, U256};
+use serde_json::Value;
+use super::function_decoder::ParamLabel;
+use super::signature_parser::{self, Signature, SignatureParam};
+use super::utils;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodeError {
+    // Index of the offending argument, None when the argument count is wrong
+    pub argument: Option<usize>,
+    // Where in the argument, e.g. "amount", "calls[1].target" or "[2]" for unnamed params
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.argument {
+            Some(_) => write!(f, "Invalid argument {}: {}", self.path, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+// Selector and arguments, from a signature such as "transfer(address to, uint256 amount)"
+pub fn encode_calldata(signature: &str, args: &[Value]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let signature = signature_parser::parse_signature(signature)?;
+    Ok(encode_call(&signature, args)?)
+}
+
+pub fn encode_call(signature: &Signature, args: &[Value]) -> Result<Vec<u8>, EncodeError> {
+    let mut calldata = utils::keccak256(signature.canonical().as_bytes())[..4].to_vec();
+    calldata.extend(encode_arguments(&signature.inputs, args)?);
+    Ok(calldata)
+}
+
+// Arguments are JSON values: numbers as JSON numbers or decimal / 0x-hex strings, fixed-point numbers
+// as decimals ("1.5"), bytes and addresses as 0x-hex, arrays as arrays and tuples as arrays or objects
+// keyed by component name. Arrays and tuples may also be given as a string holding their JSON, which
+// is what command line arguments look like.
+pub fn encode_arguments(params: &[SignatureParam], args: &[Value]) -> Result<Vec<u8>, EncodeError> {
+    if params.len() != args.len() {
+        return Err(EncodeError {
+            argument: None,
+            path: String::new(),
+            message: format!("Expected {} arguments, got {}", params.len(), args.len()),
+        });
+    }
+
+    let tokens = params
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(i, (param, arg))| {
+            let path = param.name.clone().unwrap_or_else(|| format!("[{}]", i));
+            to_token(&param.kind, &param.label(), arg, &path)
+                .map_err(|(path, message)| EncodeError { argument: Some(i), path, message })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ethabi::encode(&tokens))
+}
+
+// Errors carry the path of the innermost value that is wrong
+fn to_token(kind: &ParamType, label: &ParamLabel, value: &Value, path: &str) -> Result<Token, (String, String)> {
+    let error = |message: String| (path.to_string(), message);
+    let abi_type = label.abi_type.as_deref();
+
+    match kind {
+        ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_) if value.is_string() => {
+            let expected = if matches!(kind, ParamType::Tuple(_)) { "array or object" } else { "array" };
+            let json = serde_json::from_str::<Value>(value.as_str().unwrap_or_default())
+                .map_err(|e| error(format!("expected a JSON {}: {}", expected, e)))?;
+            to_token(kind, label, &json, path)
+        }
+        ParamType::Array(inner) | ParamType::FixedArray(inner, _) => {
+            let values = value.as_array().ok_or_else(|| error(format!("expected an array, got {}", value)))?;
+            if let ParamType::FixedArray(_, length) = kind {
+                if values.len() != *length {
+                    return Err(error(format!("expected {} elements, got {}", length, values.len())));
+                }
+            }
+            let element = ParamLabel { abi_type: abi_type.and_then(|t| t.rfind('[').map(|i| t[..i].to_string())), ..label.clone() };
+            let tokens = values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| to_token(inner, &element, value, &format!("{}[{}]", path, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(match kind {
+                ParamType::Array(_) => Token::Array(tokens),
+                _ => Token::FixedArray(tokens),
+            })
+        }
+        ParamType::Tuple(kinds) => {
+            let labels = (0..kinds.len()).map(|i| label.components.get(i).cloned().unwrap_or_default()).collect::<Vec<_>>();
+            let values = match value {
+                Value::Array(values) if values.len() == kinds.len() => values.clone(),
+                Value::Array(values) => return Err(error(format!("expected {} components, got {}", kinds.len(), values.len()))),
+                Value::Object(fields) => {
+                    if let Some(unknown) = fields.keys().find(|key| !labels.iter().any(|l| l.name.as_ref() == Some(*key))) {
+                        return Err(error(format!("unknown component `{}`", unknown)));
+                    }
+                    labels
+                        .iter()
+                        .enumerate()
+                        .map(|(i, l)| match &l.name {
+                            Some(name) => fields.get(name).cloned().ok_or_else(|| error(format!("missing component `{}`", name))),
+                            None => Err(error(format!("component {} has no name, pass the tuple as an array", i))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                other => return Err(error(format!("expected an array or object, got {}", other))),
+            };
+            let tokens = kinds
+                .iter()
+                .zip(&labels)
+                .zip(&values)
+                .enumerate()
+                .map(|(i, ((kind, label), value))| {
+                    let name = label.name.clone().unwrap_or_else(|| i.to_string());
+                    to_token(kind, label, value, &format!("{}.{}", path, name))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Token::Tuple(tokens))
+        }
+        ParamType::Address => {
+            let text = value.as_str().ok_or_else(|| error(format!("expected an address string, got {}", value)))?;
+            parse_address(text).map(Token::Address).map_err(error)
+        }
+        ParamType::Bool => match value {
+            Value::Bool(b) => Ok(Token::Bool(*b)),
+            Value::String(s) if s == "true" || s == "false" => Ok(Token::Bool(s == "true")),
+            other => Err(error(format!("expected true or false, got {}", other))),
+        },
+        ParamType::String => match value {
+            Value::String(s) => Ok(Token::String(s.clone())),
+            other => Err(error(format!("expected a string, got {}", other))),
+        },
+        ParamType::Bytes => parse_hex(value).map(Token::Bytes).map_err(error),
+        ParamType::FixedBytes(24) if abi_type == Some("function") => {
+            // Either the 24 bytes, or {"address": ..., "selector": ...} as the decoders show it
+            let value = match value {
+                Value::String(s) if s.trim_start().starts_with('{') => {
+                    serde_json::from_str(s).map_err(|e| error(format!("expected a JSON object: {}", e)))?
+                }
+                other => other.clone(),
+            };
+            let bytes = match &value {
+                Value::Object(fields) => {
+                    let address = fields.get("address").and_then(Value::as_str).ok_or_else(|| error("missing `address`".to_string()))?;
+                    let selector = parse_hex(fields.get("selector").unwrap_or(&Value::Null))
+                        .map_err(|e| error(format!("invalid `selector`: {}", e)))?;
+                    if selector.len() != 4 {
+                        return Err(error(format!("the selector must be 4 bytes, got {}", selector.len())));
+                    }
+                    let mut bytes = parse_address(address).map_err(error)?.as_bytes().to_vec();
+                    bytes.extend(selector);
+                    bytes
+                }
+                other => parse_hex(other).map_err(error)?,
+            };
+            match bytes.len() {
+                24 => Ok(Token::FixedBytes(bytes)),
+                len => Err(error(format!("expected 24 bytes (address and selector), got {}", len))),
+            }
+        }
+        ParamType::FixedBytes(size) => {
+            let bytes = parse_hex(value).map_err(error)?;
+            match bytes.len() {
+                len if len == *size => Ok(Token::FixedBytes(bytes)),
+                len => Err(error(format!("expected {} bytes, got {}", size, len))),
+            }
+        }
+        ParamType::Uint(bits) | ParamType::Int(bits) => {
+            let signed = matches!(kind, ParamType::Int(_));
+            // fixedMxN / ufixedMxN are integers scaled by 10^N
+            let decimals = abi_type.and_then(|t| t.rsplit_once('x')).and_then(|(_, n)| n.parse().ok()).unwrap_or(0);
+            let type_name = abi_type.map_or_else(|| kind.to_string(), str::to_string);
+            let (negative, magnitude) = parse_number(value, decimals).map_err(error)?;
+            let shown = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+            integer_token(negative, magnitude, signed, *bits).ok_or_else(|| error(format!("{} does not fit in {}", shown, type_name)))
+        }
+    }
+}
+
+// Two's complement for negative values, None when out of range
+fn integer_token(negative: bool, magnitude: U256, signed: bool, bits: usize) -> Option<Token> {
+    match (signed, negative) {
+        (false, true) if !magnitude.is_zero() => None,
+        (false, _) => (bits == 256 || (magnitude >> bits).is_zero()).then_some(Token::Uint(magnitude)),
+        (true, _) => {
+            let limit = U256::one() << (bits - 1);
+            let in_range = if negative { magnitude <= limit } else { magnitude < limit };
+            let value = if negative { (!magnitude).overflowing_add(U256::one()).0 } else { magnitude };
+            in_range.then_some(Token::Int(value))
+        }
+    }
+}
+
+// A JSON number, or a string holding a decimal (with up to `decimals` fractional digits) or 0x-hex number.
+// Returns the sign and the magnitude scaled by 10^decimals.
+fn parse_number(value: &Value, decimals: usize) -> Result<(bool, U256), String> {
+    let text = match value {
+        Value::Number(n) if n.is_u64() || n.is_i64() => n.to_string(),
+        // Anything else was read as an f64, which cannot hold every integer above 2^53
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if f.abs() < 9007199254740992.0 && !n.to_string().contains(['e', 'E']) => n.to_string(),
+            _ => return Err(format!("{} is not exact as a JSON number, pass large values as decimal strings", n)),
+        },
+        Value::String(s) => s.trim().replace('_', ""),
+        other => return Err(format!("expected a number, got {}", other)),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        if decimals > 0 {
+            return Err(format!("expected a decimal number, got {}", text));
+        }
+        if hex.is_empty() {
+            return Err(format!("invalid hex number {}", text));
+        }
+        return U256::from_str_radix(hex, 16).map(|n| (negative, n)).map_err(|_| format!("invalid hex number {}", text));
+    }
+
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(format!("invalid number {}", text));
+    }
+    if fraction.len() > decimals {
+        return Err(match decimals {
+            0 => format!("expected an integer, got {}", text),
+            _ => format!("{} has more than {} decimals", text, decimals),
+        });
+    }
+    let scaled = format!("{}{:0<width$}", whole, fraction, width = decimals);
+    U256::from_dec_str(&scaled).map(|n| (negative, n)).map_err(|_| format!("{} is too large", text))
+}
+
+// Mixed-case addresses have to carry a valid EIP-55 checksum
+fn parse_address(text: &str) -> Result<Address, String> {
+    let hex = utils::remove_0x_prefix(text.trim());
+    if hex.len() != 40 || text.trim().len() == hex.len() {
+        return Err(format!("expected a 0x-prefixed 20-byte address, got {}", text));
+    }
+    let bytes = hex::decode(hex).map_err(|e| format!("invalid address {}: {}", text, e))?;
+    let address = Address::from_slice(&bytes);
+    let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase()) && hex.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case && ethers::utils::to_checksum(&address, None)[2..] != *hex {
+        return Err(format!("invalid checksum in address {}", text));
+    }
+    Ok(address)
+}
+
+fn parse_hex(value: &Value) -> Result<Vec<u8>, String> {
+    let text = value.as_str().ok_or_else(|| format!("expected a 0x-hex string, got {}", value))?.trim();
+    let hex = utils::remove_0x_prefix(text);
+    if hex.len() == text.len() {
+        return Err(format!("expected a 0x-hex string, got {}", text));
+    }
+    hex::decode(hex).map_err(|e| format!("invalid hex {}: {}", text, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(signature: &str, args: Value) -> Result<String, String> {
+        let args = args.as_array().cloned().unwrap_or_default();
+        encode_calldata(signature, &args).map(hex::encode).map_err(|e| e.to_string())
+    }
+
+    fn word(calldata: &str, index: usize) -> &str {
+        &calldata[8 + index * 64..8 + (index + 1) * 64]
+    }
+
+    #[test]
+    fn encodes_transfer() {
+        let calldata = encode("transfer(address to, uint256 amount)", json!(["0x000000000000000000000000000000000000dEaD", "1_000"])).unwrap();
+        assert_eq!(
+            calldata,
+            "a9059cbb000000000000000000000000000000000000000000000000000000000000dead00000000000000000000000000000000000000000000000000000000000003e8"
+        );
+    }
+
+    #[test]
+    fn integer_bounds() {
+        assert!(encode("f(uint8)", json!([255])).is_ok());
+        assert!(encode("f(uint8)", json!([256])).unwrap_err().ends_with("256 does not fit in uint8"));
+        assert!(encode("f(uint256)", json!(["-1"])).is_err());
+        assert!(encode("f(uint256)", json!(["-0"])).is_ok());
+        assert!(encode("f(uint256)", json!([format!("0x{}", "f".repeat(64))])).is_ok());
+        assert!(encode("f(uint256)", json!([format!("0x1{}", "0".repeat(64))])).is_err());
+
+        assert!(encode("f(int8)", json!([127])).is_ok());
+        assert!(encode("f(int8)", json!([128])).is_err());
+        assert!(encode("f(int8)", json!([-129])).is_err());
+        let min = encode("f(int8)", json!([-128])).unwrap();
+        assert_eq!(word(&min, 0), format!("{}80", "f".repeat(62)));
+    }
+
+    #[test]
+    fn fixed_point_scaling() {
+        let calldata = encode("f(ufixed128x18)", json!(["1.5"])).unwrap();
+        assert_eq!(U256::from_str_radix(word(&calldata, 0), 16).unwrap(), U256::from(15) * U256::exp10(17));
+        assert!(encode("f(fixed8x1)", json!(["-12.8"])).is_ok());
+        assert!(encode("f(fixed8x1)", json!(["12.8"])).is_err());
+        assert!(encode("f(ufixed128x2)", json!(["1.234"])).unwrap_err().contains("more than 2 decimals"));
+        assert!(encode("f(ufixed128x2)", json!(["0x10"])).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_and_inexact_numbers() {
+        for value in [json!("0x"), json!("-0x"), json!("-"), json!(""), json!(".5")] {
+            assert!(encode("f(int256)", json!([value])).is_err(), "{} was accepted", value);
+        }
+        let error = encode("f(uint256)", json!([100000000000000000000000.0])).unwrap_err();
+        assert!(error.contains("pass large values as decimal strings"), "{}", error);
+        assert!(encode("f(uint256)", json!([1e18])).is_err());
+        assert!(encode("f(uint256)", json!(["100000000000000000000000"])).is_ok());
+        assert!(encode("f(uint256)", json!([u64::MAX])).is_ok());
+    }
+
+    #[test]
+    fn errors_point_at_the_argument() {
+        let error = encode(
+            "multicall((address target, bytes data)[] calls)",
+            json!([[{"target": "0x000000000000000000000000000000000000dead", "data": "0x"}, {"target": "0xdead", "data": "0x"}]]),
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Invalid argument calls[1].target:"), "{}", error);
+        assert_eq!(encode("f(uint256,bool)", json!([1])).unwrap_err(), "Expected 2 arguments, got 1");
+        assert!(encode("f(address)", json!(["0x000000000000000000000000000000000000DeAd"])).unwrap_err().contains("checksum"));
+        assert!(encode("f(bytes2)", json!(["0x010203"])).unwrap_err().contains("expected 2 bytes"));
+    }
+}
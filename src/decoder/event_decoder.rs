@@ -0,0 +1,449 @@
+use anyhow::Error;
+use ethabi::{Contract, Event, EventParam, ParamType, Token};
+use ethereum_types::H256;
+use ethers::abi::{Item, RawAbi};
+use serde_json::Value;
+use super::function_decoder::{self, Confidence};
+use super::function_decoder_with_abi;
+use super::signature_db::SignatureLookup;
+use super::signature_parser;
+use super::utils;
+
+#[derive(Debug, Clone)]
+pub struct DecodedLog {
+    pub event_name: String,
+    pub signature: String,
+    pub anonymous: bool,
+    pub params: Vec<DecodedEventParam>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedEventParam {
+    pub name: Option<String>,
+    pub kind: ParamType,
+    pub indexed: bool,
+    // The declared type when `kind` only describes its encoding, see ParamLabel::abi_type
+    pub abi_type: Option<String>,
+    // Indexed strings, bytes, arrays and tuples are only stored as the keccak hash of their encoding,
+    // in which case `value` is that hash as FixedBytes
+    pub hashed: bool,
+    pub value: Token,
+}
+
+impl DecodedEventParam {
+    pub fn type_name(&self) -> String {
+        self.abi_type.clone().unwrap_or_else(|| self.kind.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedLog {
+    pub log: DecodedLog,
+    pub confidence: Confidence,
+}
+
+pub struct EventDecoder {
+    // Loaded with fixed-point and function types replaced by their encodings, as for CalldataDecoder
+    contract: Contract,
+    // The declared types, which is what topic0 is hashed from
+    raw_abi: Vec<Item>,
+}
+
+impl EventDecoder {
+    pub fn new(abi_json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut abi = serde_json::from_str::<Value>(abi_json)?;
+        function_decoder_with_abi::replace_unsupported_types(&mut abi);
+        let contract = serde_json::from_value::<Contract>(abi)?;
+        let raw_abi = serde_json::from_str::<RawAbi>(abi_json).map(|abi| abi.into_iter().collect()).unwrap_or_default();
+        Ok(Self { contract, raw_abi })
+    }
+
+    // Declared types of the event's params, None where they are the same as the encoded ones
+    fn abi_types(&self, event: &Event) -> Vec<Option<String>> {
+        let item = self.raw_abi.iter().find(|item| {
+            item.type_field == "event"
+                && item.name.as_deref() == Some(event.name.as_str())
+                && item.inputs.iter().map(function_decoder_with_abi::encoded_type).eq(event.inputs.iter().map(|p| Some(p.kind.clone())))
+        });
+        match item {
+            Some(item) => item.inputs.iter().map(|c| function_decoder_with_abi::label_of(c).abi_type).collect(),
+            None => vec![None; event.inputs.len()],
+        }
+    }
+
+    // Matches topic0 against the ABI's events, falling back to anonymous events that fit the log exactly
+    pub fn decode_log(&self, topics: &[H256], data: &[u8]) -> Result<DecodedLog, Box<dyn std::error::Error>> {
+        let mut last_error = None;
+        if let Some(topic0) = topics.first() {
+            for event in self.contract.events().filter(|e| !e.anonymous) {
+                let abi_types = self.abi_types(event);
+                if event_topic(event, &abi_types) != *topic0 {
+                    continue;
+                }
+                match decode_log_with_types(event, &abi_types, topics, data) {
+                    Ok(decoded) => return Ok(decoded),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+        }
+
+        if let Some(decoded) = self
+            .contract
+            .events()
+            .filter(|e| e.anonymous)
+            .find_map(|event| decode_log_with_types(event, &self.abi_types(event), topics, data).ok())
+        {
+            return Ok(decoded);
+        }
+
+        match last_error {
+            Some(e) => Err(format!("Log does not match its event: {}", e).into()),
+            None => Err("Event not found in ABI".into()),
+        }
+    }
+
+    pub fn decode_log_hex(&self, topics: &[String], data: &str) -> Result<DecodedLog, Box<dyn std::error::Error>> {
+        let (topics, data) = parse_log_hex(topics, data)?;
+        self.decode_log(&topics, &data)
+    }
+}
+
+pub fn parse_log_hex(topics: &[String], data: &str) -> Result<(Vec<H256>, Vec<u8>), Box<dyn std::error::Error>> {
+    let data = hex::decode(utils::remove_0x_prefix(data.trim())).map_err(|e| format!("Invalid log data: {}", e))?;
+    Ok((parse_topics(topics)?, data))
+}
+
+pub fn parse_topics(topics: &[String]) -> Result<Vec<H256>, Box<dyn std::error::Error>> {
+    topics
+        .iter()
+        .map(|topic| {
+            let bytes = hex::decode(utils::remove_0x_prefix(topic.trim())).map_err(|e| format!("Invalid topic {}: {}", topic, e))?;
+            if bytes.len() != 32 {
+                return Err(format!("Invalid topic {}: expected 32 bytes, got {}", topic, bytes.len()).into());
+            }
+            Ok(H256::from_slice(&bytes))
+        })
+        .collect()
+}
+
+// Decodes a log against one event, keeping the declaration order of its params. Anonymous events
+// have no signature topic, and their data must decode without leftover bytes since nothing else
+// identifies them.
+pub fn decode_log_with_event(event: &Event, topics: &[H256], data: &[u8]) -> Result<DecodedLog, Box<dyn std::error::Error>> {
+    decode_log_with_types(event, &vec![None; event.inputs.len()], topics, data)
+}
+
+// `abi_types` are the declared types of the params where ethabi only knows their encoding (fixed-point
+// and function types), which the signature and topic0 are built from
+fn decode_log_with_types(
+    event: &Event,
+    abi_types: &[Option<String>],
+    topics: &[H256],
+    data: &[u8],
+) -> Result<DecodedLog, Box<dyn std::error::Error>> {
+    let topics = if event.anonymous {
+        topics
+    } else {
+        match topics.split_first() {
+            Some((topic0, rest)) if *topic0 == event_topic(event, abi_types) => rest,
+            _ => return Err("topic0 does not match the event signature".into()),
+        }
+    };
+
+    let indexed_count = event.inputs.iter().filter(|p| p.indexed).count();
+    if indexed_count != topics.len() {
+        return Err(format!("Expected {} indexed topics, got {}", indexed_count, topics.len()).into());
+    }
+
+    let data_types = event.inputs.iter().filter(|p| !p.indexed).map(|p| p.kind.clone()).collect::<Vec<_>>();
+    let data_tokens = if event.anonymous {
+        ethabi::decode_whole(&data_types, data)?
+    } else {
+        ethabi::decode(&data_types, data)?
+    };
+
+    let mut topics = topics.iter();
+    let mut data_tokens = data_tokens.into_iter();
+    let params = event
+        .inputs
+        .iter()
+        .zip(abi_types)
+        .map(|(param, abi_type)| {
+            let (hashed, value) = if param.indexed {
+                let topic = topics.next().ok_or("Missing topic")?;
+                if is_hashed_in_topic(&param.kind) {
+                    (true, Token::FixedBytes(topic.as_bytes().to_vec()))
+                } else {
+                    (false, ethabi::decode(std::slice::from_ref(&param.kind), topic.as_bytes())?.remove(0))
+                }
+            } else {
+                (false, data_tokens.next().ok_or("Missing data value")?)
+            };
+            Ok(DecodedEventParam {
+                name: Some(param.name.clone()).filter(|n| !n.is_empty()),
+                kind: param.kind.clone(),
+                indexed: param.indexed,
+                abi_type: abi_type.clone(),
+                hashed,
+                value,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+
+    Ok(DecodedLog {
+        event_name: event.name.clone(),
+        signature: event_signature(event, abi_types),
+        anonymous: event.anonymous,
+        params,
+    })
+}
+
+fn event_signature(event: &Event, abi_types: &[Option<String>]) -> String {
+    let types = event
+        .inputs
+        .iter()
+        .zip(abi_types)
+        .map(|(p, abi_type)| abi_type.clone().unwrap_or_else(|| p.kind.to_string()))
+        .collect::<Vec<_>>();
+    format!("{}({})", event.name, types.join(","))
+}
+
+fn event_topic(event: &Event, abi_types: &[Option<String>]) -> H256 {
+    H256::from(utils::keccak256(event_signature(event, abi_types).as_bytes()))
+}
+
+fn is_hashed_in_topic(kind: &ParamType) -> bool {
+    matches!(
+        kind,
+        ParamType::String | ParamType::Bytes | ParamType::Array(_) | ParamType::FixedArray(_, _) | ParamType::Tuple(_)
+    )
+}
+
+pub async fn decode_log_from_signature(topics: &[H256], data: &[u8]) -> Result<DecodedLog, Error> {
+    Ok(decode_log_candidates(topics, data, function_decoder::default_signature_lookup()?).await?.remove(0).log)
+}
+
+// Looks topic0 up as an event signature and tries every way of marking its params indexed that fits
+// the number of topics. As with calldata, only decodings that re-encode to the exact topics and data
+// are kept, best first.
+pub async fn decode_log_candidates(topics: &[H256], data: &[u8], lookup: &SignatureLookup) -> Result<Vec<RankedLog>, Error> {
+    let topic0 = topics.first().ok_or_else(|| Error::msg("Log has no topics, so its event cannot be looked up"))?;
+    let signatures = lookup.lookup_event(&format!("0x{:x}", topic0)).await?;
+    let indexed_count = topics.len() - 1;
+
+    let mut failures = Vec::new();
+    let mut decoded = Vec::new();
+    for signature in &signatures {
+        let parsed = match signature_parser::parse_signature(signature) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                failures.push(format!("{}: {}", signature, e));
+                continue;
+            }
+        };
+        let types = parsed.input_types();
+        let abi_types = parsed.inputs.iter().map(|p| p.abi_type.clone()).collect::<Vec<_>>();
+        if indexed_count > types.len().min(3) {
+            failures.push(format!("{}: too many topics for its params", signature));
+            continue;
+        }
+
+        let name = parsed.name;
+        for indexed in combinations(types.len(), indexed_count) {
+            let event = Event {
+                name: name.clone(),
+                inputs: types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, kind)| EventParam { name: String::new(), kind: kind.clone(), indexed: indexed.contains(&i) })
+                    .collect(),
+                anonymous: false,
+            };
+            match decode_exact_log(&event, &abi_types, topics, data) {
+                Ok(log) => decoded.push(log),
+                Err(e) => failures.push(format!("{} with params {:?} indexed: {}", signature, indexed, e)),
+            }
+        }
+    }
+
+    if decoded.is_empty() {
+        return Err(Error::msg(format!(
+            "Error decoding log, none of the candidate signatures match: {}",
+            failures.join("; ")
+        )));
+    }
+
+    let unique = decoded.len() == 1;
+    let mut ranked = decoded
+        .into_iter()
+        .map(|log| {
+            let confidence = match (unique, function_decoder::looks_generated(&log.event_name)) {
+                (true, _) => Confidence::High,
+                (false, false) => Confidence::Medium,
+                (false, true) => Confidence::Low,
+            };
+            RankedLog { log, confidence }
+        })
+        .collect::<Vec<_>>();
+    // Stable, so among equals the splits with indexed params first (the usual layout) stay ahead
+    ranked.sort_by_key(|candidate| std::cmp::Reverse(candidate.confidence));
+    Ok(ranked)
+}
+
+fn decode_exact_log(event: &Event, abi_types: &[Option<String>], topics: &[H256], data: &[u8]) -> Result<DecodedLog, Box<dyn std::error::Error>> {
+    let log = decode_log_with_types(event, abi_types, topics, data)?;
+
+    let data_tokens = log.params.iter().filter(|p| !p.indexed).map(|p| p.value.clone()).collect::<Vec<_>>();
+    if ethabi::encode(&data_tokens) != data {
+        return Err("re-encoding the decoded data does not reproduce the log data".into());
+    }
+    let topics_match = log
+        .params
+        .iter()
+        .filter(|p| p.indexed)
+        .zip(&topics[1..])
+        .all(|(param, topic)| param.hashed || ethabi::encode(std::slice::from_ref(&param.value)) == topic.as_bytes());
+    if !topics_match {
+        return Err("re-encoding the indexed values does not reproduce the topics".into());
+    }
+    Ok(log)
+}
+
+// Every way to pick `k` of `n` positions, in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if k > n {
+        return Vec::new();
+    }
+    (0..=n - k)
+        .flat_map(|first| {
+            combinations(n - first - 1, k - 1)
+                .into_iter()
+                .map(move |rest| std::iter::once(first).chain(rest.into_iter().map(|i| i + first + 1)).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::H160;
+    use crate::decoder::signature_db::LocalSignatureDb;
+
+    const PRICE_ABI: &str = r#"[{"type": "event", "name": "Price", "anonymous": false, "inputs": [
+        {"name": "feed", "type": "function", "indexed": true},
+        {"name": "price", "type": "ufixed128x18", "indexed": false},
+        {"name": "deltas", "type": "fixed64x2[]", "indexed": false}
+    ]}]"#;
+
+    fn word(value: i128) -> String {
+        let mut bytes = [if value < 0 { 0xff } else { 0 }; 32];
+        bytes[16..].copy_from_slice(&value.to_be_bytes());
+        hex::encode(bytes)
+    }
+
+    // Price(function,ufixed128x18,fixed64x2[]) with feed 0x..dead.0x12345678, price 1.5 and deltas [-1.5, 0.25]
+    fn price_log() -> (Vec<H256>, Vec<u8>) {
+        let topics = parse_topics(&[
+            "0x1d4240c95384d69489caed465a22280c818877e8bc23108349dab8474324bb35".to_string(),
+            "0x000000000000000000000000000000000000dead123456780000000000000000".to_string(),
+        ])
+        .unwrap();
+        let data = [word(1_500_000_000_000_000_000), word(64), word(2), word(-150), word(25)].concat();
+        (topics, hex::decode(data).unwrap())
+    }
+
+    fn assert_price(log: &DecodedLog) {
+        assert_eq!(log.signature, "Price(function,ufixed128x18,fixed64x2[])");
+        let types = log.params.iter().map(DecodedEventParam::type_name).collect::<Vec<_>>();
+        assert_eq!(types, ["function", "ufixed128x18", "fixed64x2[]"]);
+        let price = &log.params[1];
+        let value = utils::labeled_token_to_json(&price.kind, &price.value, price.abi_type.as_deref(), &[]);
+        assert_eq!(value, "1.500000000000000000");
+    }
+
+    #[test]
+    fn decodes_fixed_point_and_function_events_from_an_abi() {
+        let (topics, data) = price_log();
+        let log = EventDecoder::new(PRICE_ABI).unwrap().decode_log(&topics, &data).unwrap();
+        assert_price(&log);
+        assert_eq!(log.params[0].name.as_deref(), Some("feed"));
+    }
+
+    #[tokio::test]
+    async fn decodes_fixed_point_events_by_lookup() {
+        let mut db = LocalSignatureDb::new();
+        db.insert_event("Price(function feed, ufixed128x18 price, fixed64x2[] deltas)").unwrap();
+        let lookup = SignatureLookup::new().with_source(db);
+
+        let (topics, data) = price_log();
+        let candidates = decode_log_candidates(&topics, &data, &lookup).await.unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_price(&candidates[0].log);
+        assert!(candidates[0].log.params[0].indexed);
+    }
+
+    #[test]
+    fn decodes_transfer() {
+        let abi = r#"[{"type": "event", "name": "Transfer", "anonymous": false, "inputs": [
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}
+        ]}]"#;
+        let topics = [
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".to_string(),
+            format!("0x{}", word(0xdead)),
+            format!("0x{}", word(0xbeef)),
+        ];
+        let log = EventDecoder::new(abi).unwrap().decode_log_hex(&topics, &format!("0x{}", word(1000))).unwrap();
+        assert_eq!(log.signature, "Transfer(address,address,uint256)");
+        assert_eq!(log.params[2].value, Token::Uint(1000.into()));
+        assert!(log.params.iter().all(|p| p.abi_type.is_none()));
+    }
+
+    #[test]
+    fn decodes_anonymous_events() {
+        let abi = r#"[{"type": "event", "name": "Deposit", "anonymous": true, "inputs": [
+            {"name": "account", "type": "address", "indexed": true},
+            {"name": "id", "type": "bytes32", "indexed": true},
+            {"name": "amount", "type": "uint256", "indexed": false}
+        ]}]"#;
+        let decoder = EventDecoder::new(abi).unwrap();
+        // No signature topic, every topic is an indexed arg
+        let topics = [format!("0x{}", word(0xdead)), format!("0x{}", "11".repeat(32))];
+        let log = decoder.decode_log_hex(&topics, &format!("0x{}", word(7))).unwrap();
+        assert!(log.anonymous);
+        assert_eq!(log.signature, "Deposit(address,bytes32,uint256)");
+        let values = log.params.iter().map(|p| p.value.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [Token::Address(H160::from_low_u64_be(0xdead)), Token::FixedBytes(vec![0x11; 32]), Token::Uint(7.into())]
+        );
+
+        // Nothing else identifies the event, so the data has to fit it exactly
+        assert!(decoder.decode_log_hex(&topics, &format!("0x{}{}", word(7), word(8))).is_err());
+        assert!(decoder.decode_log_hex(&topics[..1], &format!("0x{}", word(7))).is_err());
+    }
+
+    #[test]
+    fn indexed_strings_and_bytes_are_hashes() {
+        let abi = r#"[{"type": "event", "name": "Named", "anonymous": false, "inputs": [
+            {"name": "name", "type": "string", "indexed": true},
+            {"name": "payload", "type": "bytes", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}
+        ]}]"#;
+        let name_hash = H256(utils::keccak256(b"alice"));
+        let payload_hash = H256(utils::keccak256(&[0x01, 0x02]));
+        let topics = [H256(utils::keccak256(b"Named(string,bytes,uint256)")), name_hash, payload_hash];
+        let data = hex::decode(word(3)).unwrap();
+
+        let log = EventDecoder::new(abi).unwrap().decode_log(&topics, &data).unwrap();
+        assert!(log.params[0].hashed && log.params[1].hashed && !log.params[2].hashed);
+        assert_eq!(log.params[0].value, Token::FixedBytes(name_hash.as_bytes().to_vec()));
+        assert_eq!(log.params[1].value, Token::FixedBytes(payload_hash.as_bytes().to_vec()));
+        assert_eq!(log.params[0].kind, ParamType::String);
+        assert_eq!(log.params[2].value, Token::Uint(3.into()));
+    }
+}
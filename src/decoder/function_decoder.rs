@@ -1,237 +1,294 @@
-use serde::Deserialize;
 use anyhow::{Error, Result};
-use crate::utils;
+use serde::Serialize;
+use super::call_tree::{self, CallTree};
+use super::signature_db::SignatureLookup;
+use super::signature_parser::{self, SignatureParam};
+use super::strict_decoder::{self, EncodingViolation};
+use super::utils;
 use ethabi::Token;
 use ethabi::ParamType;
 use tiny_keccak::{Hasher, Keccak};
-use hex;
-use regex::Regex;
 
-#[derive(Deserialize, Debug)]
-struct SignatureResponse {
-    count: usize,
-    results: Vec<FunctionSignature>,
+lazy_static::lazy_static! {
+    // Built once from SIGNATURE_DB / SIGNATURE_LOOKUP_REMOTE, see SignatureLookup::from_env
+    static ref DEFAULT_LOOKUP: Result<SignatureLookup, String> = SignatureLookup::from_env().map_err(|e| e.to_string());
 }
 
-#[derive(Deserialize, Debug)]
-struct FunctionSignature {
-    text_signature: String,
+pub fn default_signature_lookup() -> Result<&'static SignatureLookup, Error> {
+    DEFAULT_LOOKUP.as_ref().map_err(|e| Error::msg(e.clone()))
 }
 
-pub async fn final_result_from_calldata(call_data: &str) -> Result<(), Error> {
-    // Check if the call_data is empty or too short
-    if call_data.is_empty() || call_data.len() < 10 {
-        println!("Invalid calldata, nothing to decode.");
+// Result of decoding calldata, shared by the selector-lookup and ABI decoders
+#[derive(Debug, Clone)]
+pub struct DecodedCall {
+    pub function_name: String,
+    pub signature: String,
+    pub params: Vec<DecodedParam>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedParam {
+    // Only known when decoding with an ABI
+    pub name: Option<String>,
+    pub kind: ParamType,
+    // The ABI's internalType, e.g. "struct Types.OutputProposal" or "contract IERC20"
+    pub internal_type: Option<String>,
+    // See ParamLabel::abi_type
+    pub abi_type: Option<String>,
+    pub value: Token,
+    // Names of the tuple's components (or of the tuple inside an array), empty when unknown
+    pub components: Vec<ParamLabel>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamLabel {
+    pub name: Option<String>,
+    pub internal_type: Option<String>,
+    // The declared type when ethabi has no equivalent and the decoded kind is only its encoding, e.g.
+    // "fixed128x18" decoded as int128, "function" as bytes24, or "(ufixed64x2,bool)[]"
+    pub abi_type: Option<String>,
+    pub components: Vec<ParamLabel>,
+}
+
+impl DecodedParam {
+    pub fn type_name(&self) -> String {
+        self.abi_type.clone().unwrap_or_else(|| self.kind.to_string())
+    }
+}
+
+// Values returned by a function call, i.e. decoded eth_call output
+#[derive(Debug, Clone)]
+pub struct DecodedReturn {
+    pub function_name: String,
+    pub signature: String,
+    pub values: Vec<DecodedParam>,
+}
+
+// How sure we are that a looked-up signature is the one the calldata was built for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedCall {
+    pub call: DecodedCall,
+    pub confidence: Confidence,
+}
+
+pub async fn decode_calldata_from_selector(call_data: &str) -> Result<DecodedCall, Error> {
+    decode_calldata_with_lookup(call_data, default_signature_lookup()?).await
+}
+
+// Best ranked candidate only
+pub async fn decode_calldata_with_lookup(call_data: &str, lookup: &SignatureLookup) -> Result<DecodedCall, Error> {
+    Ok(decode_calldata_candidates(call_data, lookup).await?.remove(0).call)
+}
+
+// Best candidate, plus the calls wrapped in its arguments (multicall, Safe transactions, router commands...)
+pub async fn decode_call_tree(call_data: &str, lookup: &SignatureLookup) -> Result<CallTree, Error> {
+    let calldata = hex::decode(utils::remove_0x_prefix(call_data.trim()))?;
+    call_tree::decode_call_tree(&calldata, None, Some(lookup)).await
+}
+
+// Decodes the calldata against every candidate signature for its selector. Only candidates whose
+// decoded values re-encode to exactly the same bytes are kept, best first.
+pub async fn decode_calldata_candidates(call_data: &str, lookup: &SignatureLookup) -> Result<Vec<RankedCall>, Error> {
+    // Malformed calldata is rejected before the lookup, which may go to the network
+    let call_data = call_data.trim();
+    let calldata = hex::decode(utils::remove_0x_prefix(call_data))?;
+    if calldata.len() < 4 {
         return Err(Error::msg("No valid function in calldata."));
     }
 
-    let function_selector = match get_selector_from_call_data(call_data) {
-        Ok(selector) => selector,
-        Err(e) => {
-            println!("Error extracting function selector: {}", e);
-            return Err(e);
-        }
-    };
+    let function_selector = format!("0x{}", hex::encode(&calldata[..4]));
+    let signatures = lookup.lookup(&function_selector).await?;
+    rank_candidates(call_data, &calldata, &signatures)
+}
 
-    let function_signature = match get_function_signature(&function_selector).await {
-        Ok(signature) => signature,
-        Err(e) => {
-            println!("Error fetching function signature: {}", e);
-            return Err(e);
+// The ranking part of decode_calldata_candidates, for callers that looked the signatures up themselves
+pub(crate) fn rank_candidates(call_data: &str, calldata: &[u8], signatures: &[String]) -> Result<Vec<RankedCall>, Error> {
+    let mut failures = Vec::new();
+    let mut decoded = Vec::new();
+    for signature in signatures {
+        match decode_exact(signature, call_data, &calldata[4..]) {
+            Ok(call) => decoded.push(call),
+            Err(e) => failures.push(format!("{}: {}", signature, e)),
         }
-    };
+    }
 
-    if function_signature.is_empty() {
-        println!("No function signature found");
-        return Err(Error::msg("No function signature found"));
+    if decoded.is_empty() {
+        return Err(Error::msg(format!(
+            "Error decoding calldata, none of the candidate signatures match: {}",
+            failures.join("; ")
+        )));
     }
 
-    let err = decode_without_abi(function_signature.as_str(), call_data).err(); 
-    if let Some(err) = err {
-        println!("Error decoding calldata: {:?}", err);
-        return Err(Error::msg("Error decoding calldata"));
+    let unique = decoded.len() == 1;
+    let mut ranked = decoded
+        .into_iter()
+        .map(|call| RankedCall { confidence: candidate_confidence(unique, &call), call })
+        .collect::<Vec<_>>();
+    // Stable, so candidates of equal confidence keep the order the signature source gave them
+    ranked.sort_by_key(|candidate| std::cmp::Reverse(candidate.confidence));
+    Ok(ranked)
+}
+
+// Strict counterpart of decode_calldata_candidates: candidates are kept when the calldata decodes against
+// them at all, with the ways it departs from their canonical encoding. Fewest violations first, so a
+// signature the calldata was built for ranks above one it only happens to decode against.
+pub async fn decode_calldata_strict(call_data: &str, lookup: &SignatureLookup) -> Result<Vec<(RankedCall, Vec<EncodingViolation>)>, Error> {
+    let function_selector = get_selector_from_call_data(call_data)?;
+    let signatures = lookup.lookup(&function_selector).await?;
+
+    let mut failures = Vec::new();
+    let mut decoded = Vec::new();
+    for signature in &signatures {
+        match CalldataDecoder::new(signature).and_then(|decoder| decoder.decode_call_strict(call_data)) {
+            Ok(call) => decoded.push(call),
+            Err(e) => failures.push(format!("{}: {}", signature, e)),
+        }
+    }
+    if decoded.is_empty() {
+        return Err(Error::msg(format!("Error decoding calldata, none of the candidate signatures match: {}", failures.join("; "))));
     }
 
-    Ok(())
+    let unique = decoded.len() == 1;
+    let mut ranked = decoded
+        .into_iter()
+        .map(|(call, violations)| (RankedCall { confidence: candidate_confidence(unique, &call), call }, violations))
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|(candidate, violations)| (violations.len(), std::cmp::Reverse(candidate.confidence)));
+    Ok(ranked)
 }
 
+fn candidate_confidence(unique: bool, call: &DecodedCall) -> Confidence {
+    match (unique, looks_generated(&call.function_name)) {
+        (true, _) => Confidence::High,
+        (false, false) => Confidence::Medium,
+        (false, true) => Confidence::Low,
+    }
+}
+
+fn decode_exact(signature: &str, call_data: &str, params: &[u8]) -> Result<DecodedCall, Box<dyn std::error::Error>> {
+    let call = CalldataDecoder::new(signature)?.decode_call(call_data)?;
+    let tokens = call.params.iter().map(|param| param.value.clone()).collect::<Vec<_>>();
+    if ethabi::encode(&tokens) != params {
+        return Err("re-encoding the decoded values does not reproduce the calldata".into());
+    }
+    Ok(call)
+}
+
+// Collision spam on 4byte tends to use names like "watch_tg_invmru_119a5a98"
+pub(crate) fn looks_generated(function_name: &str) -> bool {
+    let has_hex_segment = function_name
+        .split('_')
+        .any(|part| part.len() >= 6 && part.chars().all(|c| c.is_ascii_hexdigit()) && part.chars().any(|c| c.is_ascii_digit()));
+    has_hex_segment || function_name.matches('_').count() >= 3
+}
 
 pub fn get_selector_from_call_data(call_data: &str) -> Result<String, Error> {
-    let call_data = utils::remove_0x_prefix(call_data);
-    if call_data.len() < 8 {
+    let calldata = hex::decode(utils::remove_0x_prefix(call_data.trim()))?;
+    if calldata.len() < 4 {
         return Err(Error::msg("Call data is too short"));
     }
-    let function_selector = &call_data[0..8];
-    Ok(format!("0x{}", function_selector))
+    Ok(format!("0x{}", hex::encode(&calldata[..4])))
 }
 
 pub async fn get_function_signature(function_selector: &str) -> Result<String, Error> {
-    let url = format!("https://www.4byte.directory/api/v1/signatures/?hex_signature={}", function_selector);
-    let response = reqwest::get(&url).await?
-        .json::<SignatureResponse>()
-        .await?;
-
-    if response.count == 0 {
-        println!("No function signature found");
-        return Err(Error::msg("No function signature found"));
-    }
+    Ok(get_function_signatures(function_selector).await?.remove(0))
+}
 
-    Ok(response.results[0].text_signature.clone())
+// Every known candidate for the selector, from the first signature source that has any
+pub async fn get_function_signatures(function_selector: &str) -> Result<Vec<String>, Error> {
+    default_signature_lookup()?.lookup(function_selector).await
 }
 
 pub struct CalldataDecoder {
     function_signature: String,
     param_types: Vec<ParamType>,
+    params: Vec<SignatureParam>,
+    // From an optional "returns (...)" or "-> type" suffix; "-> (uint256,bool)" means two return values
+    returns: Option<Vec<SignatureParam>>,
 }
 
 impl CalldataDecoder {
+    // Accepts anything signature_parser does, so param names given in the signature show up in the decoded params
     pub fn new(function_signature: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let (param_types, _) = Self::parse_signature(function_signature)?;
+        let signature = signature_parser::parse_signature(function_signature)?;
         Ok(Self {
-            function_signature: function_signature.to_string(),
-            param_types,
+            // Canonical form, which is what the selector is computed from
+            function_signature: signature.canonical(),
+            param_types: signature.input_types(),
+            params: signature.inputs,
+            returns: signature.outputs,
         })
     }
 
     pub fn decode_calldata(&self, calldata: &str) -> Result<Vec<Token>, Box<dyn std::error::Error>> {
-        let calldata = hex::decode(calldata.strip_prefix("0x").unwrap_or(calldata))?;
-        
-        // Verify function selector
-        let expected_selector = self.compute_function_selector(&self.function_signature);
-        if calldata[..4] != expected_selector {
-            return Err("Function selector mismatch".into());
-        }
+        let calldata = self.checked_calldata(calldata)?;
 
         // Decode parameters
         ethabi::decode(&self.param_types, &calldata[4..]).map_err(|e| e.into())
     }
 
-    fn parse_signature(signature: &str) -> Result<(Vec<ParamType>, Option<ParamType>), Box<dyn std::error::Error>> {
-        let re = Regex::new(r"(\w+)\((.*)\)(\s*->\s*(.+))?").unwrap();
-        let caps = re.captures(signature).ok_or("Invalid function signature")?;
-
-        let params_str = caps.get(2).map_or("", |m| m.as_str());
-        let return_type_str = caps.get(4).map(|m| m.as_str());
-
-        let param_types = params_str
-            .split(',')
-            .filter(|s| !s.is_empty())
-            .map(Self::parse_type)
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let return_type = return_type_str.map(Self::parse_type).transpose()?;
-
-        Ok((param_types, return_type))
+    pub fn decode_call(&self, calldata: &str) -> Result<DecodedCall, Box<dyn std::error::Error>> {
+        let tokens = self.decode_calldata(calldata)?;
+        Ok(self.call_from_tokens(tokens))
     }
 
-    fn parse_type(typ: &str) -> Result<ParamType, Box<dyn std::error::Error>> {
-        let typ = typ.trim();
-
-        // Check for array types first
-        if typ.ends_with("[]") {
-            let inner = Self::parse_type(&typ[..typ.len() - 2])?;
-            return Ok(ParamType::Array(Box::new(inner)));
-        }
+    // Also decodes calldata that is not canonically encoded, and reports where it is not
+    pub fn decode_call_strict(&self, calldata: &str) -> Result<(DecodedCall, Vec<EncodingViolation>), Box<dyn std::error::Error>> {
+        let calldata = self.checked_calldata(calldata)?;
+        let names = self.params.iter().enumerate().map(|(i, p)| p.name.clone().unwrap_or_else(|| format!("[{}]", i))).collect::<Vec<_>>();
+        let (tokens, violations) = strict_decoder::decode_strict(&self.param_types, &names, &calldata[4..], 4)?;
+        Ok((self.call_from_tokens(tokens), violations))
+    }
 
-        // Check for fixed-size array
-        if let Some(captures) = Regex::new(r"^(.+)\[(\d+)\]$")?.captures(typ) {
-            let inner = Self::parse_type(captures.get(1).unwrap().as_str())?;
-            let size = captures.get(2).unwrap().as_str().parse::<usize>()?;
-            return Ok(ParamType::FixedArray(Box::new(inner), size));
+    // The calldata bytes, once the selector is known to match
+    fn checked_calldata(&self, calldata: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let calldata = hex::decode(calldata.strip_prefix("0x").unwrap_or(calldata))?;
+        if calldata.len() < 4 {
+            return Err("Calldata is too short".into());
         }
 
-        // Parse other types
-        match typ {
-            "address" => Ok(ParamType::Address),
-            "bool" => Ok(ParamType::Bool),
-            "string" => Ok(ParamType::String),
-            "bytes" => Ok(ParamType::Bytes),
-
-            // Unsigned integers
-            t if t.starts_with("uint") => {
-                let size = t[4..].parse::<usize>().map_err(|_| "Invalid uint size")?;
-                if size % 8 == 0 && size <= 256 {
-                    Ok(ParamType::Uint(size))
-                } else {
-                    Err("Invalid uint size".into())
-                }
-            },
-
-            // Signed integers
-            t if t.starts_with("int") => {
-                let size = t[3..].parse::<usize>().map_err(|_| "Invalid int size")?;
-                if size % 8 == 0 && size <= 256 {
-                    Ok(ParamType::Int(size))
-                } else {
-                    Err("Invalid int size".into())
-                }
-            },
-
-            // Fixed-size bytes
-            t if t.starts_with("bytes") => {
-                if t == "bytes" {
-                    Ok(ParamType::Bytes)
-                } else {
-                    let size = t[5..].parse::<usize>().map_err(|_| "Invalid bytes size")?;
-                    if size > 0 && size <= 32 {
-                        Ok(ParamType::FixedBytes(size))
-                    } else {
-                        Err("Invalid fixed bytes size".into())
-                    }
-                }
-            },
-
-            // Tuples (e.g., "(uint256,address)")
-            t if t.starts_with("(") && t.ends_with(")") => {
-                let inner = &t[1..t.len() - 1];
-                let inner_types = Self::split_tuple_types(inner)?
-                    .into_iter()
-                    .map(|t| Self::parse_type(&t))
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(ParamType::Tuple(inner_types))
-            },
-
-            // Add support for fixed point numbers if needed
-            // "fixed" | "ufixed" => {...}
-
-            _ => Err(format!("Unsupported type: {}", typ).into()),
+        // Verify function selector
+        let expected_selector = self.compute_function_selector(&self.function_signature);
+        if calldata[..4] != expected_selector {
+            return Err("Function selector mismatch".into());
         }
+        Ok(calldata)
     }
 
-    fn split_tuple_types(tuple_str: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let mut result = Vec::new();
-        let mut current = String::new();
-        let mut paren_count = 0;
-
-        for ch in tuple_str.chars() {
-            match ch {
-                '(' => {
-                    paren_count += 1;
-                    current.push(ch);
-                },
-                ')' => {
-                    paren_count -= 1;
-                    current.push(ch);
-                },
-                ',' if paren_count == 0 => {
-                    if !current.is_empty() {
-                        result.push(current.trim().to_string());
-                        current.clear();
-                    }
-                },
-                _ => current.push(ch),
-            }
-        }
-
-        if !current.is_empty() {
-            result.push(current.trim().to_string());
+    fn call_from_tokens(&self, tokens: Vec<Token>) -> DecodedCall {
+        let function_name = self.function_signature
+            .split('(')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        DecodedCall {
+            function_name,
+            signature: self.function_signature.clone(),
+            params: decoded_params(&self.params, tokens),
         }
+    }
 
-        if paren_count != 0 {
-            return Err("Mismatched parentheses in tuple type".into());
-        }
+    // Needs the signature to declare what it returns, e.g. "balanceOf(address) -> uint256"
+    pub fn decode_output(&self, return_data: &str) -> Result<DecodedReturn, Box<dyn std::error::Error>> {
+        let returns = self.returns.as_ref().ok_or("Signature does not declare a return type")?;
+        let data = hex::decode(return_data.trim().strip_prefix("0x").unwrap_or(return_data.trim()))?;
+        let tokens = ethabi::decode(&returns.iter().map(|p| p.kind.clone()).collect::<Vec<_>>(), &data)?;
 
-        Ok(result)
+        Ok(DecodedReturn {
+            function_name: self.function_signature.split('(').next().unwrap_or_default().to_string(),
+            signature: self.function_signature.clone(),
+            values: decoded_params(returns, tokens),
+        })
     }
 
     fn compute_function_selector(&self, signature: &str) -> [u8; 4] {
@@ -243,32 +300,116 @@ impl CalldataDecoder {
     }
 }
 
-// Helper function to convert ethabi::Token to a more readable format
-pub fn token_to_string(token: &Token) -> String {
-    match token {
-        Token::Address(address) => format!("Address: 0x{:x}", address),
-        Token::Uint(uint) => format!("Uint: {}", uint),
-        Token::Int(int) => format!("Int: {}", int),
-        Token::Bool(b) => format!("Bool: {}", b),
-        Token::String(s) => format!("String: {}", s),
-        Token::Bytes(bytes) => format!("Bytes: 0x{}", hex::encode(bytes)),
-        Token::FixedBytes(bytes) => format!("FixedBytes: 0x{}", hex::encode(bytes)),
-        Token::Array(tokens) => format!("Array: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
-        Token::FixedArray(tokens) => format!("FixedArray: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
-        Token::Tuple(tokens) => format!("Tuple: ({})", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
-    }
+fn decoded_params(params: &[SignatureParam], tokens: Vec<Token>) -> Vec<DecodedParam> {
+    params
+        .iter()
+        .zip(tokens)
+        .map(|(param, value)| DecodedParam {
+            name: param.name.clone(),
+            kind: param.kind.clone(),
+            internal_type: None,
+            abi_type: param.abi_type.clone(),
+            value,
+            components: param.components.clone(),
+        })
+        .collect()
 }
 
-// Example usage
-fn decode_without_abi(function_signature: &str, calldata: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let decoder = CalldataDecoder::new(function_signature)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use crate::decoder::signature_db::{LocalSignatureDb, SignatureSource};
 
-    let decoded_params = decoder.decode_calldata(calldata)?;
+    // All of these share transfer's selector 0xa9059cbb
+    const COLLISIONS: &[&str] = &["func_2093253501(bytes)", "many_msg_babbage(bytes1)", "transfer(address,uint256)"];
 
-    println!("Decoded parameters:");
-    for (i, param) in decoded_params.iter().enumerate() {
-        println!("  Parameter {}: {}", i, token_to_string(param));
+    fn colliding_lookup() -> SignatureLookup {
+        let mut db = LocalSignatureDb::new();
+        for signature in COLLISIONS {
+            assert_eq!(db.insert(signature).unwrap(), "0xa9059cbb");
+        }
+        SignatureLookup::new().with_source(db)
     }
 
-    Ok(())
-}
\ No newline at end of file
+    fn word(value: u8) -> String {
+        format!("{:064x}", value)
+    }
+
+    // Fails the test if the calldata gets as far as a lookup
+    struct NoLookup;
+
+    impl SignatureSource for NoLookup {
+        fn name(&self) -> &str {
+            "none"
+        }
+
+        fn lookup<'a>(&'a self, selector: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+            panic!("looked up {}", selector)
+        }
+    }
+
+    #[test]
+    fn selector_from_calldata() {
+        assert_eq!(get_selector_from_call_data("0xa9059cbb0000").unwrap(), "0xa9059cbb");
+        assert_eq!(get_selector_from_call_data(" A9059CBB ").unwrap(), "0xa9059cbb");
+        assert!(get_selector_from_call_data("0x1234567é12").is_err());
+        assert!(get_selector_from_call_data("0x123456").is_err());
+        assert!(get_selector_from_call_data("0x1234567").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_calldata_before_the_lookup() {
+        let lookup = SignatureLookup::new().with_source(NoLookup);
+        for call_data in ["0xa9059cbb00zz", "0xa9059cbb0", "0x1234567é12", "0xa90593", ""] {
+            assert!(decode_calldata_candidates(call_data, &lookup).await.is_err(), "{}", call_data);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_single_exact_match_is_high_confidence() {
+        let call_data = format!("0xa9059cbb{}{}", "00".repeat(12) + &"11".repeat(20), word(5));
+        let candidates = decode_calldata_candidates(&call_data, &colliding_lookup()).await.unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].call.signature, "transfer(address,uint256)");
+        assert_eq!(candidates[0].confidence, Confidence::High);
+
+        // bytes1 takes the first byte of the address word and ignores the rest, so it decodes but is dropped
+        assert!(CalldataDecoder::new("many_msg_babbage(bytes1)").unwrap().decode_call(&call_data).is_ok());
+    }
+
+    #[tokio::test]
+    async fn generated_names_rank_below_real_ones() {
+        // Both an address and a uint, and an empty bytes value behind its offset
+        let call_data = format!("0xa9059cbb{}{}", word(0x20), word(0));
+        let candidates = decode_calldata_candidates(&call_data, &colliding_lookup()).await.unwrap();
+        let ranked = candidates.iter().map(|c| (c.call.signature.as_str(), c.confidence)).collect::<Vec<_>>();
+        assert_eq!(ranked, [("transfer(address,uint256)", Confidence::Medium), ("func_2093253501(bytes)", Confidence::Low)]);
+
+        assert!(looks_generated("func_2093253501"));
+        assert!(looks_generated("watch_tg_invmru_119a5a98"));
+        assert!(!looks_generated("many_msg_babbage"));
+        assert!(!looks_generated("swapExactTokensForTokens"));
+    }
+
+    #[test]
+    fn decodes_output_from_a_signature() {
+        let decoder = CalldataDecoder::new("getReserves() -> (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)").unwrap();
+        let data = format!("0x{}{}{}", word(10), word(20), word(30));
+        let output = decoder.decode_output(&data).unwrap();
+        assert_eq!((output.function_name.as_str(), output.signature.as_str()), ("getReserves", "getReserves()"));
+        let names = output.values.iter().map(|v| v.name.as_deref()).collect::<Vec<_>>();
+        assert_eq!(names, [Some("reserve0"), Some("reserve1"), Some("blockTimestampLast")]);
+        let values = output.values.iter().map(|v| v.value.clone()).collect::<Vec<_>>();
+        assert_eq!(values, [Token::Uint(10.into()), Token::Uint(20.into()), Token::Uint(30.into())]);
+
+        // A single unnamed return value
+        let output = CalldataDecoder::new("balanceOf(address) returns (uint256)").unwrap().decode_output(&word(7)).unwrap();
+        assert_eq!(output.values[0].name, None);
+        assert_eq!(output.values[0].value, Token::Uint(7.into()));
+
+        let error = CalldataDecoder::new("balanceOf(address)").unwrap().decode_output(&data).unwrap_err();
+        assert_eq!(error.to_string(), "Signature does not declare a return type");
+        assert!(decoder.decode_output(&word(10)).is_err());
+    }
+}
@@ -1,46 +1,335 @@
-use ethabi::{Contract, Token};
-use hex;
+use ethabi::{Contract, Function, Param, Token};
+use ethers::abi::{Component, Item, RawAbi};
+use serde_json::Value;
+use super::function_decoder::{DecodedCall, DecodedParam, DecodedReturn, ParamLabel};
+use super::call_tree::{self, CallTree};
+use super::revert_decoder::{self, DecodedRevert, RevertKind};
+use super::signature_db::SignatureLookup;
+use super::calldata_encoder;
+use super::signature_parser::{self, Signature, SignatureParam};
+use super::strict_decoder::{self, EncodingViolation};
+use super::utils;
 
 pub struct CalldataDecoder {
+    // Loaded with fixed-point and function types replaced by their encodings, which ethabi lacks
     contract: Contract,
+    // ethabi drops the names of tuple components and the declared types, so they are looked up in the raw ABI
+    raw_abi: Vec<Item>,
 }
 
 impl CalldataDecoder {
     pub fn new(abi_json: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let contract = Contract::load(abi_json.as_bytes())?;
-        Ok(Self { contract })
+        let mut abi = serde_json::from_str::<Value>(abi_json)?;
+        replace_unsupported_types(&mut abi);
+        let contract = serde_json::from_value::<Contract>(abi)?;
+        let raw_abi = serde_json::from_str::<RawAbi>(abi_json).map(|abi| abi.into_iter().collect()).unwrap_or_default();
+        Ok(Self { contract, raw_abi })
     }
 
     pub fn decode_calldata(&self, calldata: &str) -> Result<(String, Vec<Token>), Box<dyn std::error::Error>> {
+        let decoded = self.decode_call(calldata)?;
+        Ok((decoded.function_name, decoded.params.into_iter().map(|p| p.value).collect()))
+    }
+
+    pub fn decode_call(&self, calldata: &str) -> Result<DecodedCall, Box<dyn std::error::Error>> {
+        let (function, calldata) = self.called_function(calldata)?;
+
+        // Decode the parameters
+        let decoded = function.decode_input(&calldata[4..])?;
+
+        Ok(self.call_from_tokens(function, decoded))
+    }
+
+    // Also decodes calldata that is not canonically encoded, and reports where it is not
+    pub fn decode_call_strict(&self, calldata: &str) -> Result<(DecodedCall, Vec<EncodingViolation>), Box<dyn std::error::Error>> {
+        let (function, calldata) = self.called_function(calldata)?;
+        let types = function.inputs.iter().map(|p| p.kind.clone()).collect::<Vec<_>>();
+        let names = function
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| if p.name.is_empty() { format!("[{}]", i) } else { p.name.clone() })
+            .collect::<Vec<_>>();
+        let (decoded, violations) = strict_decoder::decode_strict(&types, &names, &calldata[4..], 4)?;
+        Ok((self.call_from_tokens(function, decoded), violations))
+    }
+
+    fn called_function(&self, calldata: &str) -> Result<(&Function, Vec<u8>), Box<dyn std::error::Error>> {
         let calldata = hex::decode(calldata.strip_prefix("0x").unwrap_or(calldata))?;
-        
+        if calldata.len() < 4 {
+            return Err("Calldata is too short".into());
+        }
+
         // Extract function selector (first 4 bytes)
         let function_selector = &calldata[0..4];
 
         // Find the matching function in the ABI
         let function = self.contract.functions().find(|f| {
-            f.short_signature() == function_selector
+            self.selector("function", &f.name, &f.inputs) == function_selector
         }).ok_or("Function not found in ABI")?;
+        Ok((function, calldata))
+    }
 
-        // Decode the parameters
-        let decoded = function.decode_input(&calldata[4..])?;
+    fn call_from_tokens(&self, function: &Function, tokens: Vec<Token>) -> DecodedCall {
+        DecodedCall {
+            function_name: function.name.clone(),
+            signature: self.signature("function", &function.name, &function.inputs),
+            params: self.decoded_params("function", &function.name, &function.inputs, false, tokens),
+        }
+    }
+
+    // Also decodes the calls nested in the arguments. Those whose selector is not in the ABI are
+    // looked up when a lookup is given, and left undecoded otherwise.
+    pub async fn decode_call_tree(&self, calldata: &str, lookup: Option<&SignatureLookup>) -> Result<CallTree, Box<dyn std::error::Error>> {
+        // The outer call has to be in the ABI
+        self.decode_call(calldata)?;
+        let calldata = hex::decode(calldata.trim().strip_prefix("0x").unwrap_or(calldata.trim()))?;
+        Ok(call_tree::decode_call_tree(&calldata, Some(self), lookup).await?)
+    }
+
+    // `function` is a name, a full signature like "getL2Output(uint256)" for overloaded functions, or a 0x selector
+    pub fn decode_output(&self, function: &str, return_data: &str) -> Result<DecodedReturn, Box<dyn std::error::Error>> {
+        let function = self.find_function(function)?;
+        let data = hex::decode(return_data.trim().strip_prefix("0x").unwrap_or(return_data.trim()))?;
+        let decoded = function.decode_output(&data)?;
+
+        Ok(DecodedReturn {
+            function_name: function.name.clone(),
+            signature: self.signature("function", &function.name, &function.inputs),
+            values: self.decoded_params("function", &function.name, &function.inputs, true, decoded),
+        })
+    }
+
+    // Calldata for one of the ABI's functions, see calldata_encoder::encode_arguments for the argument
+    // format. `function` is found as for decode_output. Also returns the canonical signature.
+    pub fn encode_call(&self, function: &str, args: &[Value]) -> Result<(String, Vec<u8>), Box<dyn std::error::Error>> {
+        let function = self.find_function(function)?;
+        // The raw ABI has the component names tuples can be given as objects with, and the declared types
+        let components = self.raw_item("function", &function.name, &function.inputs).map_or(&[][..], |item| item.inputs.as_slice());
+        let inputs = function.inputs.iter().enumerate().map(|(i, param)| signature_param(components.get(i), param)).collect();
+        let signature = Signature { name: function.name.clone(), inputs, outputs: None };
+        let calldata = calldata_encoder::encode_call(&signature, args)?;
+        Ok((signature.canonical(), calldata))
+    }
+
+    // Error(string) and Panic(uint256) are understood without the ABI, anything else has to be one of its errors
+    pub fn decode_revert(&self, revert_data: &str) -> Result<DecodedRevert, Box<dyn std::error::Error>> {
+        let data = hex::decode(revert_data.trim().strip_prefix("0x").unwrap_or(revert_data.trim()))?;
+        if let Some(decoded) = revert_decoder::decode_standard_revert(&data)? {
+            return Ok(decoded);
+        }
+
+        let error = self.contract.errors().find(|e| self.selector("error", &e.name, &e.inputs) == data[..4])
+            .ok_or("Error not found in ABI")?;
+        let decoded = error.decode(&data[4..])?;
+
+        Ok(DecodedRevert {
+            kind: RevertKind::Custom,
+            name: error.name.clone(),
+            signature: self.signature("error", &error.name, &error.inputs),
+            params: self.decoded_params("error", &error.name, &error.inputs, false, decoded),
+            reason: None,
+            confidence: None,
+        })
+    }
+
+    fn find_function(&self, function: &str) -> Result<&Function, Box<dyn std::error::Error>> {
+        let function = function.trim();
+        let selector = function.strip_prefix("0x").and_then(|s| hex::decode(s).ok()).filter(|s| s.len() == 4);
+        if let Some(selector) = selector {
+            return self.contract.functions().find(|f| self.selector("function", &f.name, &f.inputs) == selector[..])
+                .ok_or_else(|| format!("Function {} not found in ABI", function).into());
+        }
+
+        if function.contains('(') {
+            let signature: String = function.chars().filter(|c| !c.is_whitespace()).collect();
+            return self.contract.functions().find(|f| self.signature("function", &f.name, &f.inputs) == signature)
+                .ok_or_else(|| format!("Function {} not found in ABI", function).into());
+        }
+
+        match self.contract.functions_by_name(function).map_err(|_| format!("Function {} not found in ABI", function))?.as_slice() {
+            [function] => Ok(function),
+            overloads => Err(format!(
+                "{} is overloaded ({}), pass the full signature or the selector",
+                function,
+                overloads.iter().map(|f| self.signature("function", &f.name, &f.inputs)).collect::<Vec<_>>().join(", ")
+            ).into()),
+        }
+    }
+
+    fn raw_item(&self, item_type: &str, name: &str, inputs: &[Param]) -> Option<&Item> {
+        self.raw_abi.iter().find(|item| {
+            item.type_field == item_type
+                && item.name.as_deref() == Some(name)
+                && item.inputs.iter().map(encoded_type).eq(inputs.iter().map(|p| Some(p.kind.clone())))
+        })
+    }
+
+    // With the declared types, which is what the selector is hashed from
+    fn signature(&self, item_type: &str, name: &str, inputs: &[Param]) -> String {
+        match self.raw_item(item_type, name, inputs) {
+            Some(item) => format!("{}({})", name, item.inputs.iter().map(component_type).collect::<Vec<_>>().join(",")),
+            None => signature_of(name, inputs),
+        }
+    }
 
-        Ok((function.name.clone(), decoded))
+    fn selector(&self, item_type: &str, name: &str, inputs: &[Param]) -> [u8; 4] {
+        let hash = utils::keccak256(self.signature(item_type, name, inputs).as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    // Pairs decoded tokens with their ABI params, including tuple component names from the raw ABI
+    fn decoded_params(&self, item_type: &str, name: &str, inputs: &[Param], outputs: bool, tokens: Vec<Token>) -> Vec<DecodedParam> {
+        let item = self.raw_item(item_type, name, inputs);
+        let (params, components) = match (item, outputs) {
+            (Some(item), true) => (self.output_params(name, inputs), item.outputs.as_slice()),
+            (Some(item), false) => (inputs.to_vec(), item.inputs.as_slice()),
+            (None, true) => (self.output_params(name, inputs), &[][..]),
+            (None, false) => (inputs.to_vec(), &[][..]),
+        };
+
+        params
+            .iter()
+            .zip(tokens)
+            .enumerate()
+            .map(|(i, (param, value))| {
+                let label = components.get(i).map(label_of).unwrap_or_default();
+                DecodedParam {
+                    name: Some(param.name.clone()).filter(|n| !n.is_empty()),
+                    kind: param.kind.clone(),
+                    internal_type: label.internal_type,
+                    abi_type: label.abi_type,
+                    value,
+                    components: label.components,
+                }
+            })
+            .collect()
+    }
+
+    fn output_params(&self, name: &str, inputs: &[Param]) -> Vec<Param> {
+        self.contract
+            .functions_by_name(name)
+            .ok()
+            .and_then(|functions| functions.iter().find(|f| f.inputs == inputs))
+            .map(|f| f.outputs.clone())
+            .unwrap_or_default()
     }
 }
 
-// Helper function to convert ethabi::Token to a more readable format
-pub fn token_to_string(token: &Token) -> String {
-    match token {
-        Token::Address(address) => format!("Address: 0x{:x}", address),
-        Token::Uint(uint) => format!("Uint: {}", uint),
-        Token::Int(int) => format!("Int: {}", int),
-        Token::Bool(b) => format!("Bool: {}", b),
-        Token::String(s) => format!("String: {}", s),
-        Token::Bytes(bytes) => format!("Bytes: 0x{}", hex::encode(bytes)),
-        Token::FixedBytes(bytes) => format!("FixedBytes: 0x{}", hex::encode(bytes)),
-        Token::Array(tokens) => format!("Array: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
-        Token::FixedArray(tokens) => format!("FixedArray: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
-        Token::Tuple(tokens) => format!("Tuple: ({})", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
+fn signature_of(name: &str, params: &[Param]) -> String {
+    let types = params.iter().map(|p| p.kind.to_string()).collect::<Vec<_>>();
+    format!("{}({})", name, types.join(","))
+}
+
+// Canonical type of a raw ABI param, e.g. "tuple[]" with components becomes "(uint256,address)[]"
+fn component_type(component: &Component) -> String {
+    match component.type_field.strip_prefix("tuple") {
+        Some(suffix) => format!(
+            "({}){}",
+            component.components.iter().map(component_type).collect::<Vec<_>>().join(","),
+            suffix
+        ),
+        None => component.type_field.clone(),
     }
-}
\ No newline at end of file
+}
+
+// The ParamType a raw ABI param decodes as, None when its type is invalid
+pub(crate) fn encoded_type(component: &Component) -> Option<ethabi::ParamType> {
+    signature_parser::parse_type(&component_type(component)).ok()
+}
+
+fn signature_param(component: Option<&Component>, param: &Param) -> SignatureParam {
+    let label = component.map(label_of).unwrap_or_default();
+    SignatureParam {
+        name: Some(param.name.clone()).filter(|n| !n.is_empty()),
+        kind: param.kind.clone(),
+        indexed: false,
+        abi_type: label.abi_type,
+        components: label.components,
+    }
+}
+
+pub(crate) fn label_of(component: &Component) -> ParamLabel {
+    let declared = component_type(component);
+    ParamLabel {
+        name: Some(component.name.clone()).filter(|n| !n.is_empty()),
+        internal_type: component.internal_type.clone(),
+        abi_type: encoded_type(component).filter(|kind| kind.to_string() != declared).map(|_| declared),
+        components: component.components.iter().map(label_of).collect(),
+    }
+}
+
+// ethabi cannot load fixedMxN, ufixedMxN and function params, so their types are replaced by the ones
+// they are encoded as (intM, uintM and bytes24), array suffixes included
+pub(crate) fn replace_unsupported_types(abi: &mut Value) {
+    for item in abi.as_array_mut().into_iter().flatten() {
+        for key in ["inputs", "outputs"] {
+            if let Some(params) = item.get_mut(key).and_then(Value::as_array_mut) {
+                params.iter_mut().for_each(replace_param_type);
+            }
+        }
+    }
+}
+
+fn replace_param_type(param: &mut Value) {
+    if let Some(Value::String(kind)) = param.get_mut("type") {
+        if kind.starts_with("fixed") || kind.starts_with("ufixed") || kind.starts_with("function") {
+            if let Ok(encoded) = signature_parser::parse_type(kind) {
+                *kind = encoded.to_string();
+            }
+        }
+    }
+    if let Some(components) = param.get_mut("components").and_then(Value::as_array_mut) {
+        components.iter_mut().for_each(replace_param_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::U256;
+
+    const PAIR_ABI: &str = r#"[
+        {"type": "function", "name": "getReserves", "inputs": [], "outputs": [
+            {"name": "reserve0", "type": "uint112"},
+            {"name": "reserve1", "type": "uint112"},
+            {"name": "blockTimestampLast", "type": "uint32"}
+        ], "stateMutability": "view"},
+        {"type": "function", "name": "balanceOf", "inputs": [{"name": "owner", "type": "address"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "balanceOf", "inputs": [{"name": "owner", "type": "address"}, {"name": "id", "type": "uint256"}], "outputs": [{"name": "", "type": "uint256"}], "stateMutability": "view"}
+    ]"#;
+
+    fn return_data(values: &[u64]) -> String {
+        format!("0x{}", hex::encode(ethabi::encode(&values.iter().map(|v| Token::Uint(U256::from(*v))).collect::<Vec<_>>())))
+    }
+
+    #[test]
+    fn decodes_output() {
+        let decoder = CalldataDecoder::new(PAIR_ABI).unwrap();
+        let data = return_data(&[1000, 2000, 1_700_000_000]);
+        for function in ["getReserves", "getReserves()", "0x0902f1ac"] {
+            let output = decoder.decode_output(function, &data).unwrap();
+            assert_eq!(output.signature, "getReserves()");
+            let names = output.values.iter().map(|v| v.name.as_deref()).collect::<Vec<_>>();
+            assert_eq!(names, [Some("reserve0"), Some("reserve1"), Some("blockTimestampLast")]);
+            let values = output.values.iter().map(|v| v.value.clone()).collect::<Vec<_>>();
+            assert_eq!(values, [Token::Uint(1000.into()), Token::Uint(2000.into()), Token::Uint(1_700_000_000.into())]);
+            assert_eq!(output.values[2].kind, ethabi::ParamType::Uint(32));
+        }
+
+        let output = decoder.decode_output("balanceOf(address,uint256)", &return_data(&[5])).unwrap();
+        assert_eq!(output.values[0].name, None);
+        assert_eq!(output.values[0].value, Token::Uint(5.into()));
+    }
+
+    #[test]
+    fn output_errors() {
+        let decoder = CalldataDecoder::new(PAIR_ABI).unwrap();
+        let data = return_data(&[1]);
+        assert_eq!(decoder.decode_output("totalSupply", &data).unwrap_err().to_string(), "Function totalSupply not found in ABI");
+        assert_eq!(decoder.decode_output("0x18160ddd", &data).unwrap_err().to_string(), "Function 0x18160ddd not found in ABI");
+        let overloaded = decoder.decode_output("balanceOf", &data).unwrap_err().to_string();
+        assert!(overloaded.contains("overloaded (balanceOf(address), balanceOf(address,uint256))"), "{}", overloaded);
+        assert!(decoder.decode_output("getReserves", &data).is_err());
+    }
+}
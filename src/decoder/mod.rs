@@ -1,6 +1,13 @@
-mod function_decoder;
-mod txn_decoder;
-mod utils;
-mod function_decoder_with_abi;
-mod main;
-use hex;
\ No newline at end of file
+pub mod function_decoder;
+pub mod txn_decoder;
+pub mod txn_encoder;
+pub mod utils;
+pub mod function_decoder_with_abi;
+pub mod signature_db;
+pub mod event_decoder;
+pub mod revert_decoder;
+pub mod call_tree;
+pub mod abi_guesser;
+pub mod signature_parser;
+pub mod strict_decoder;
+pub mod calldata_encoder;
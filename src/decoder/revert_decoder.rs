@@ -0,0 +1,191 @@
+use anyhow::Error;
+use ethabi::{ParamType, Token};
+use ethereum_types::U256;
+use serde::Serialize;
+use super::function_decoder::{self, Confidence, DecodedParam};
+use super::signature_db::SignatureLookup;
+use super::utils;
+
+// Error(string), what require(cond, "reason") and revert("reason") produce
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// Panic(uint256), what failing asserts, overflows, out-of-bounds accesses etc. produce
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RevertKind {
+    // Plain revert() or require(cond) without a reason
+    Empty,
+    Error,
+    Panic,
+    Custom,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedRevert {
+    pub kind: RevertKind,
+    pub name: String,
+    pub signature: String,
+    pub params: Vec<DecodedParam>,
+    // The Error(string) message, or what a panic code means
+    pub reason: Option<String>,
+    // Only set for custom errors identified by selector lookup
+    pub confidence: Option<Confidence>,
+}
+
+// Meaning of the Panic(uint256) codes emitted by solc
+pub fn panic_reason(code: U256) -> String {
+    let reason = match code.low_u64() {
+        _ if code > U256::from(0xff) => None,
+        0x00 => Some("generic compiler inserted panic"),
+        0x01 => Some("assertion failed"),
+        0x11 => Some("arithmetic overflow or underflow"),
+        0x12 => Some("division or modulo by zero"),
+        0x21 => Some("conversion of a value that is out of range into an enum"),
+        0x22 => Some("access to an incorrectly encoded storage byte array"),
+        0x31 => Some("pop() on an empty array"),
+        0x32 => Some("array index out of bounds"),
+        0x41 => Some("too much memory allocated or array too large"),
+        0x51 => Some("call to a zero-initialized internal function variable"),
+        _ => None,
+    };
+    reason.map_or_else(|| format!("unknown panic code 0x{:x}", code), |r| r.to_string())
+}
+
+// Handles the payloads every contract can produce: empty reverts, Error(string) and Panic(uint256).
+// Returns None for anything else, which has to be a custom error.
+pub fn decode_standard_revert(data: &[u8]) -> Result<Option<DecodedRevert>, Box<dyn std::error::Error>> {
+    if data.is_empty() {
+        return Ok(Some(DecodedRevert {
+            kind: RevertKind::Empty,
+            name: String::new(),
+            signature: String::new(),
+            params: Vec::new(),
+            reason: None,
+            confidence: None,
+        }));
+    }
+    if data.len() < 4 {
+        return Err(format!("Revert data is too short: {} bytes", data.len()).into());
+    }
+
+    let (kind, name, param_type) = match data[..4].try_into()? {
+        ERROR_SELECTOR => (RevertKind::Error, "Error", ParamType::String),
+        PANIC_SELECTOR => (RevertKind::Panic, "Panic", ParamType::Uint(256)),
+        _ => return Ok(None),
+    };
+
+    let value = ethabi::decode(std::slice::from_ref(&param_type), &data[4..])
+        .map_err(|e| format!("Malformed {} revert: {}", name, e))?
+        .remove(0);
+    let reason = match &value {
+        Token::String(message) => message.clone(),
+        Token::Uint(code) => panic_reason(*code),
+        _ => unreachable!("decoded as {}", param_type),
+    };
+
+    Ok(Some(DecodedRevert {
+        kind,
+        name: name.to_string(),
+        signature: format!("{}({})", name, param_type),
+        params: vec![DecodedParam { name: None, kind: param_type, internal_type: None, abi_type: None, value, components: Vec::new() }],
+        reason: Some(reason),
+        confidence: None,
+    }))
+}
+
+// Standard reverts are decoded directly, custom errors by looking their selector up like calldata
+pub async fn decode_revert(revert_data: &str, lookup: &SignatureLookup) -> Result<DecodedRevert, Error> {
+    let data = hex::decode(utils::remove_0x_prefix(revert_data.trim()))?;
+    if let Some(decoded) = decode_standard_revert(&data).map_err(|e| Error::msg(e.to_string()))? {
+        return Ok(decoded);
+    }
+
+    let best = function_decoder::decode_calldata_candidates(&format!("0x{}", hex::encode(&data)), lookup)
+        .await?
+        .remove(0);
+    Ok(DecodedRevert {
+        kind: RevertKind::Custom,
+        name: best.call.function_name,
+        signature: best.call.signature,
+        params: best.call.params,
+        reason: None,
+        confidence: Some(best.confidence),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::function_decoder_with_abi::CalldataDecoder;
+    use crate::decoder::signature_db::LocalSignatureDb;
+
+    fn revert_data(selector: &[u8], tokens: &[Token]) -> Vec<u8> {
+        [selector, &ethabi::encode(tokens)].concat()
+    }
+
+    fn lookup() -> SignatureLookup {
+        SignatureLookup::new().with_source(LocalSignatureDb::new())
+    }
+
+    #[test]
+    fn error_string() {
+        let data = revert_data(&ERROR_SELECTOR, &[Token::String("Ownable: caller is not the owner".into())]);
+        let decoded = decode_standard_revert(&data).unwrap().unwrap();
+        assert_eq!(decoded.kind, RevertKind::Error);
+        assert_eq!(decoded.signature, "Error(string)");
+        assert_eq!(decoded.reason.as_deref(), Some("Ownable: caller is not the owner"));
+
+        assert!(decode_standard_revert(&ERROR_SELECTOR).is_err());
+    }
+
+    #[test]
+    fn panic_codes() {
+        let data = revert_data(&PANIC_SELECTOR, &[Token::Uint(0x11.into())]);
+        let decoded = decode_standard_revert(&data).unwrap().unwrap();
+        assert_eq!(decoded.kind, RevertKind::Panic);
+        assert_eq!(decoded.signature, "Panic(uint256)");
+        assert_eq!(decoded.params[0].value, Token::Uint(0x11.into()));
+        assert_eq!(decoded.reason.as_deref(), Some("arithmetic overflow or underflow"));
+
+        assert_eq!(panic_reason(0x99.into()), "unknown panic code 0x99");
+        // Codes past 64 bits must not alias the known ones through their low bits
+        assert_eq!(panic_reason((U256::one() << 64) + 0x11), "unknown panic code 0x10000000000000011");
+    }
+
+    #[tokio::test]
+    async fn empty_and_short_data() {
+        let decoded = decode_revert("0x", &lookup()).await.unwrap();
+        assert_eq!(decoded.kind, RevertKind::Empty);
+        assert!(decoded.params.is_empty() && decoded.reason.is_none());
+
+        let error = decode_revert("0x08c379", &lookup()).await.unwrap_err();
+        assert_eq!(error.to_string(), "Revert data is too short: 3 bytes");
+    }
+
+    #[tokio::test]
+    async fn custom_errors() {
+        let abi = r#"[{"type": "error", "name": "InsufficientBalance", "inputs": [
+            {"name": "available", "type": "uint256"}, {"name": "required", "type": "uint256"}
+        ]}]"#;
+        let selector = &utils::keccak256(b"InsufficientBalance(uint256,uint256)")[..4];
+        let data = format!("0x{}", hex::encode(revert_data(selector, &[Token::Uint(1.into()), Token::Uint(2.into())])));
+
+        let decoded = CalldataDecoder::new(abi).unwrap().decode_revert(&data).unwrap();
+        assert_eq!(decoded.kind, RevertKind::Custom);
+        assert_eq!(decoded.signature, "InsufficientBalance(uint256,uint256)");
+        assert_eq!(decoded.params.iter().map(|p| p.name.as_deref()).collect::<Vec<_>>(), [Some("available"), Some("required")]);
+        assert_eq!(decoded.params[1].value, Token::Uint(2.into()));
+        assert!(decoded.confidence.is_none());
+
+        let mut db = LocalSignatureDb::new();
+        db.insert("InsufficientBalance(uint256,uint256)").unwrap();
+        let decoded = decode_revert(&data, &SignatureLookup::new().with_source(db)).await.unwrap();
+        assert_eq!(decoded.kind, RevertKind::Custom);
+        assert_eq!(decoded.name, "InsufficientBalance");
+        assert_eq!(decoded.params[0].value, Token::Uint(1.into()));
+        assert_eq!(decoded.confidence, Some(Confidence::High));
+
+        assert!(decode_revert(&data, &lookup()).await.is_err());
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Error, Result};
+use futures::future::{BoxFuture, FutureExt};
+use serde::Deserialize;
+use serde_json::Value;
+use super::signature_parser;
+use super::utils;
+
+// Common signatures shipped with the crate so well-known selectors resolve without network access
+const BUNDLED_SIGNATURES: &str = include_str!("signatures.txt");
+
+const FOURBYTE_URL: &str = "https://www.4byte.directory/api/v1/";
+
+// Remote lookups are on by default, so without network access they have to give up rather than hang
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Anything that can map a selector ("0x" + 8 hex digits) to candidate text signatures.
+// Sources that also know events map a full topic0 hash to event signatures.
+pub trait SignatureSource: Send + Sync {
+    fn name(&self) -> &str;
+    fn lookup<'a>(&'a self, selector: &'a str) -> BoxFuture<'a, Result<Vec<String>>>;
+
+    fn lookup_event<'a>(&'a self, _topic: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        futures::future::ready(Ok(Vec::new())).boxed()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LocalSignatureDb {
+    signatures: HashMap<String, Vec<String>>,
+    events: HashMap<String, Vec<String>>,
+}
+
+impl LocalSignatureDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bundled() -> Self {
+        let mut db = Self::new();
+        db.load_text(BUNDLED_SIGNATURES).expect("bundled signature list is valid");
+        db
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let mut db = Self::new();
+        db.load_file(path)?;
+        Ok(db)
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.values().chain(self.events.values()).map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty() && self.events.is_empty()
+    }
+
+    // Adds a signature under the selector computed from it; returns the selector
+    pub fn insert(&mut self, signature: &str) -> Result<String> {
+        let (hash, signature) = hash_of(signature)?;
+        let selector = format!("0x{}", hex::encode(&hash[..4]));
+        add_candidate(&mut self.signatures, &selector, signature);
+        Ok(selector)
+    }
+
+    // Adds an event signature under its topic0 hash; returns the topic
+    pub fn insert_event(&mut self, signature: &str) -> Result<String> {
+        let (hash, signature) = hash_of(signature)?;
+        let topic = format!("0x{}", hex::encode(hash));
+        add_candidate(&mut self.events, &topic, signature);
+        Ok(topic)
+    }
+
+    pub fn get(&self, selector: &str) -> &[String] {
+        self.signatures
+            .get(&normalize_selector(selector))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn get_event(&self, topic: &str) -> &[String] {
+        self.events
+            .get(&normalize_selector(topic))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // JSON dumps are detected by their first character, anything else is read as text
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Error::msg(format!("Failed to read signature database {}: {}", path.display(), e)))?;
+        match contents.trim_start().chars().next() {
+            Some('{') | Some('[') => self.load_json(&contents),
+            _ => self.load_text(&contents),
+        }
+        .map_err(|e| Error::msg(format!("{}: {}", path.display(), e)))
+    }
+
+    // One signature per line, optionally preceded by its selector ("0xa9059cbb transfer(address,uint256)").
+    // Event signatures are prefixed with "event " or preceded by their 32-byte topic hash.
+    // Blank lines and lines starting with '#' are ignored.
+    pub fn load_text(&mut self, contents: &str) -> Result<usize> {
+        let mut count = 0;
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (selector, signature) = match line.split_once(|c: char| c.is_whitespace() || c == ',') {
+                Some((first, rest)) if !first.contains('(') && first != "event" => (Some(first), rest.trim()),
+                _ => (None, line),
+            };
+            self.add_checked(selector, signature)
+                .map_err(|e| Error::msg(format!("line {}: {}", i + 1, e)))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // Accepts a 4byte.directory API page ({"results": [{"hex_signature", "text_signature"}]}),
+    // a plain array of those entries or of signature strings, or an object mapping selectors
+    // to a signature or a list of signatures.
+    pub fn load_json(&mut self, contents: &str) -> Result<usize> {
+        #[derive(Deserialize)]
+        struct Entry {
+            hex_signature: Option<String>,
+            text_signature: String,
+        }
+
+        let value: Value = serde_json::from_str(contents)?;
+        let entries = match value {
+            Value::Object(mut object) if object.contains_key("results") => object.remove("results").unwrap_or_default(),
+            Value::Object(object) => {
+                let mut count = 0;
+                for (selector, signatures) in object {
+                    let signatures = match signatures {
+                        Value::String(signature) => vec![signature],
+                        other => serde_json::from_value::<Vec<String>>(other)
+                            .map_err(|e| Error::msg(format!("{}: {}", selector, e)))?,
+                    };
+                    for signature in signatures {
+                        self.add_checked(Some(&selector), &signature)?;
+                        count += 1;
+                    }
+                }
+                return Ok(count);
+            }
+            other => other,
+        };
+
+        let entries = match entries {
+            Value::Array(entries) => entries,
+            _ => return Err(Error::msg("Expected an array of signatures")),
+        };
+        for entry in &entries {
+            match entry {
+                Value::String(signature) => self.add_checked(None, signature)?,
+                other => {
+                    let entry = serde_json::from_value::<Entry>(other.clone())?;
+                    self.add_checked(entry.hex_signature.as_deref(), &entry.text_signature)?
+                }
+            }
+        }
+        Ok(entries.len())
+    }
+
+    // Event signatures are recognised by an "event " prefix or a full 32-byte hash as the selector
+    fn add_checked(&mut self, selector: Option<&str>, signature: &str) -> Result<()> {
+        let selector = selector.map(normalize_selector);
+        let (is_event, signature) = match signature.strip_prefix("event ") {
+            Some(event) => (true, event.trim_start()),
+            None => (selector.as_ref().is_some_and(|s| s.len() == 66), signature),
+        };
+        let (hash, _) = hash_of(signature)?;
+        let computed = if is_event {
+            format!("0x{}", hex::encode(hash))
+        } else {
+            format!("0x{}", hex::encode(&hash[..4]))
+        };
+        match selector {
+            Some(selector) if selector != computed => {
+                Err(Error::msg(format!("Selector {} does not match {} ({})", selector, signature, computed)))
+            }
+            _ if is_event => self.insert_event(signature).map(|_| ()),
+            _ => self.insert(signature).map(|_| ()),
+        }
+    }
+}
+
+impl SignatureSource for LocalSignatureDb {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn lookup<'a>(&'a self, selector: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        futures::future::ready(Ok(self.get(selector).to_vec())).boxed()
+    }
+
+    fn lookup_event<'a>(&'a self, topic: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        futures::future::ready(Ok(self.get_event(topic).to_vec())).boxed()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct SignatureResponse {
+    results: Vec<FunctionSignature>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FunctionSignature {
+    text_signature: String,
+}
+
+pub struct FourByteSource {
+    url: String,
+    // Only known when built from the API base, a bare signatures endpoint says nothing about events
+    event_url: Option<String>,
+}
+
+impl FourByteSource {
+    pub fn new() -> Self {
+        Self::with_api_url(FOURBYTE_URL)
+    }
+
+    // For mirrors of the 4byte.directory API, `url` being the function signatures endpoint
+    pub fn with_url(url: &str) -> Self {
+        Self { url: url.to_string(), event_url: None }
+    }
+
+    // For mirrors serving the whole API, `url` being the base both endpoints are under
+    pub fn with_api_url(url: &str) -> Self {
+        let base = url.trim_end_matches('/');
+        Self {
+            url: format!("{}/signatures/", base),
+            event_url: Some(format!("{}/event-signatures/", base)),
+        }
+    }
+
+    async fn query(url: &str, hex_signature: &str) -> Result<Vec<String>> {
+        let response = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?
+            .get(url)
+            .query(&[("hex_signature", normalize_selector(hex_signature))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SignatureResponse>()
+            .await?;
+        Ok(response.results.into_iter().map(|s| s.text_signature).collect())
+    }
+}
+
+impl Default for FourByteSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignatureSource for FourByteSource {
+    fn name(&self) -> &str {
+        "4byte.directory"
+    }
+
+    fn lookup<'a>(&'a self, selector: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        Self::query(&self.url, selector).boxed()
+    }
+
+    fn lookup_event<'a>(&'a self, topic: &'a str) -> BoxFuture<'a, Result<Vec<String>>> {
+        match &self.event_url {
+            Some(url) => Self::query(url, topic).boxed(),
+            None => futures::future::ready(Ok(Vec::new())).boxed(),
+        }
+    }
+}
+
+// Sources are consulted in order and the first one with any candidates wins
+#[derive(Default)]
+pub struct SignatureLookup {
+    sources: Vec<Box<dyn SignatureSource>>,
+}
+
+impl SignatureLookup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_source(mut self, source: impl SignatureSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    // Bundled signatures, plus the files listed in SIGNATURE_DB (separated like PATH), then
+    // 4byte.directory unless SIGNATURE_LOOKUP_REMOTE is "0" or "false"
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_with(&[], false)
+    }
+
+    // Same as from_env with extra database files, and the option to skip the remote lookup regardless of the environment
+    pub fn from_env_with(extra_paths: &[PathBuf], offline: bool) -> Result<Self> {
+        let mut db = LocalSignatureDb::bundled();
+        let env_paths = std::env::var_os("SIGNATURE_DB")
+            .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for path in env_paths.iter().chain(extra_paths) {
+            db.load_file(path)?;
+        }
+
+        let lookup = Self::new().with_source(db);
+        let remote = std::env::var("SIGNATURE_LOOKUP_REMOTE").unwrap_or_default();
+        if offline || matches!(remote.to_lowercase().as_str(), "0" | "false" | "no" | "off") {
+            Ok(lookup)
+        } else {
+            Ok(lookup.with_source(FourByteSource::new()))
+        }
+    }
+
+    pub async fn lookup(&self, selector: &str) -> Result<Vec<String>> {
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match source.lookup(selector).await {
+                Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+            }
+        }
+        Err(not_found("function", selector, &errors))
+    }
+
+    pub async fn lookup_event(&self, topic: &str) -> Result<Vec<String>> {
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match source.lookup_event(topic).await {
+                Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+            }
+        }
+        Err(not_found("event", topic, &errors))
+    }
+}
+
+fn not_found(kind: &str, selector: &str, errors: &[String]) -> Error {
+    if errors.is_empty() {
+        Error::msg(format!("No {} signature found for {}", kind, selector))
+    } else {
+        Error::msg(format!("No {} signature found for {} ({})", kind, selector, errors.join("; ")))
+    }
+}
+
+fn normalize_selector(selector: &str) -> String {
+    format!("0x{}", utils::remove_0x_prefix(selector.trim()).to_lowercase())
+}
+
+fn add_candidate(map: &mut HashMap<String, Vec<String>>, key: &str, signature: String) {
+    let candidates = map.entry(key.to_string()).or_default();
+    if !candidates.contains(&signature) {
+        candidates.push(signature);
+    }
+}
+
+// Signatures are hashed and stored in canonical form, so "transfer(address to, uint256)" is stored as
+// "transfer(address,uint256)"
+fn hash_of(signature: &str) -> Result<([u8; 32], String)> {
+    let signature = signature_parser::parse_signature(signature)
+        .map_err(|e| Error::msg(format!("Invalid signature {}", e)))?
+        .canonical();
+    Ok((utils::keccak256(signature.as_bytes()), signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_params_hash_like_the_canonical_signature() {
+        let mut db = LocalSignatureDb::new();
+        assert_eq!(db.insert("transfer(address to, uint256 amount)").unwrap(), "0xa9059cbb");
+        assert_eq!(db.get("0xa9059cbb"), ["transfer(address,uint256)"]);
+
+        let topic = db.insert_event("Transfer(address indexed from, address indexed to, uint256 value)").unwrap();
+        assert_eq!(topic, "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+    }
+
+    #[test]
+    fn bundled_signatures_parse() {
+        assert!(!LocalSignatureDb::bundled().is_empty());
+    }
+
+    #[test]
+    fn rejects_selectors_that_do_not_match() {
+        let mut db = LocalSignatureDb::new();
+        assert!(db.load_text("0xa9059cbb transfer(address to, uint256 amount)").is_ok());
+        assert!(db.load_text("0x12345678 transfer(address,uint256)").is_err());
+        assert!(db.load_text("transfer(address,uint7)").is_err());
+    }
+
+    #[test]
+    fn loads_json_dumps() {
+        let mut db = LocalSignatureDb::new();
+        let page = r#"{"count": 2, "results": [
+            {"id": 1, "hex_signature": "0xa9059cbb", "text_signature": "transfer(address,uint256)"},
+            {"id": 2, "hex_signature": "0x095ea7b3", "text_signature": "approve(address,uint256)"}
+        ]}"#;
+        assert_eq!(db.load_json(page).unwrap(), 2);
+        assert_eq!(db.get("0x095EA7B3"), ["approve(address,uint256)"]);
+
+        let list = r#"["balanceOf(address owner)", {"text_signature": "event Approval(address indexed,address indexed,uint256)"}]"#;
+        assert_eq!(db.load_json(list).unwrap(), 2);
+        assert_eq!(db.get("0x70a08231"), ["balanceOf(address)"]);
+        assert_eq!(db.get_event("0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925"), ["Approval(address,address,uint256)"]);
+
+        let by_selector = r#"{"0x18160ddd": "totalSupply()", "0xa9059cbb": ["transfer(address,uint256)"]}"#;
+        assert_eq!(db.load_json(by_selector).unwrap(), 2);
+        assert_eq!(db.get("0xa9059cbb"), ["transfer(address,uint256)"]);
+        assert_eq!(db.len(), 5);
+
+        assert!(db.load_json(r#"{"0x12345678": "totalSupply()"}"#).is_err());
+        assert!(db.load_json(r#"{"results": {}}"#).is_err());
+        assert!(db.load_json("[").is_err());
+    }
+}
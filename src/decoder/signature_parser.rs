@@ -0,0 +1,459 @@
+use std::fmt;
+use ethabi::ParamType;
+use super::function_decoder::ParamLabel;
+
+// A human-readable signature such as "transfer(address to, uint256 amount)",
+// "function getL2Output(uint256) external view returns ((bytes32,uint128,uint128))" or
+// "balanceOf(address) -> uint256"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub inputs: Vec<SignatureParam>,
+    // From "returns (...)" or "-> ...", where "-> (uint256,bool)" means two return values
+    pub outputs: Option<Vec<SignatureParam>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureParam {
+    pub name: Option<String>,
+    pub kind: ParamType,
+    pub indexed: bool,
+    // The declared type when `kind` only describes its encoding, see ParamLabel::abi_type
+    pub abi_type: Option<String>,
+    // Names given to tuple components inline, e.g. "(address to, uint256 value)[] calls"
+    pub components: Vec<ParamLabel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    // Byte offset into `input` where parsing failed
+    pub position: usize,
+    pub input: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {} in `{}`", self.message, self.position, self.input)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Signature {
+    // name(type,...), which is what selectors and topics are hashed from
+    pub fn canonical(&self) -> String {
+        format!("{}({})", self.name, self.inputs.iter().map(SignatureParam::type_name).collect::<Vec<_>>().join(","))
+    }
+
+    pub fn input_types(&self) -> Vec<ParamType> {
+        self.inputs.iter().map(|p| p.kind.clone()).collect()
+    }
+}
+
+impl SignatureParam {
+    pub fn label(&self) -> ParamLabel {
+        ParamLabel {
+            name: self.name.clone(),
+            internal_type: None,
+            abi_type: self.abi_type.clone(),
+            components: self.components.clone(),
+        }
+    }
+
+    pub fn type_name(&self) -> String {
+        self.abi_type.clone().unwrap_or_else(|| self.kind.to_string())
+    }
+}
+
+// Words that may follow the parameter list and carry nothing the decoders need
+const MODIFIERS: &[&str] = &[
+    "external", "public", "internal", "private", "view", "pure", "payable", "nonpayable", "constant", "anonymous",
+    "virtual", "override",
+];
+
+// And those that may follow a parameter's type
+const LOCATIONS: &[&str] = &["memory", "calldata", "storage"];
+
+pub fn parse_signature(input: &str) -> Result<Signature, ParseError> {
+    let mut parser = Parser::new(input);
+    let signature = parser.signature()?;
+    parser.skip_whitespace();
+    parser.eat(';');
+    parser.skip_whitespace();
+    if !parser.at_end() {
+        return Err(parser.error("unexpected trailing input"));
+    }
+    Ok(signature)
+}
+
+// A single type such as "uint256", "(address,bytes)[]" or "tuple(uint256 a, bool b)"
+pub fn parse_type(input: &str) -> Result<ParamType, ParseError> {
+    let mut parser = Parser::new(input);
+    let (kind, _, _) = parser.param_type()?;
+    parser.skip_whitespace();
+    if !parser.at_end() {
+        return Err(parser.error("unexpected trailing input"));
+    }
+    Ok(kind)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn signature(&mut self) -> Result<Signature, ParseError> {
+        self.skip_whitespace();
+        let mut name = self.identifier();
+        if matches!(name.as_str(), "function" | "event" | "error") && self.peek_after_whitespace().is_some_and(is_identifier_start) {
+            self.skip_whitespace();
+            name = self.identifier();
+        }
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+
+        self.skip_whitespace();
+        self.expect('(')?;
+        let inputs = self.param_list()?;
+
+        let mut outputs = None;
+        loop {
+            self.skip_whitespace();
+            if self.input[self.position..].starts_with("->") {
+                self.position += 2;
+                outputs = Some(self.arrow_outputs()?);
+                break;
+            }
+            let word_start = self.position;
+            let word = self.identifier();
+            match word.as_str() {
+                "" => break,
+                "returns" => {
+                    self.skip_whitespace();
+                    self.expect('(')?;
+                    outputs = Some(self.param_list()?);
+                    break;
+                }
+                w if MODIFIERS.contains(&w) => {}
+                _ => {
+                    self.position = word_start;
+                    return Err(self.error(format!("unexpected `{}`", word)));
+                }
+            }
+        }
+
+        Ok(Signature { name, inputs, outputs })
+    }
+
+    // "-> uint256", "-> (uint256,bool)" for two values, or "-> (uint256,bool)[]" for one array
+    fn arrow_outputs(&mut self) -> Result<Vec<SignatureParam>, ParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some('(') {
+            let checkpoint = self.position;
+            self.position += 1;
+            let params = self.param_list()?;
+            if self.peek_after_whitespace() != Some('[') {
+                return Ok(params);
+            }
+            self.position = checkpoint;
+        }
+        let param = self.param()?;
+        Ok(vec![param])
+    }
+
+    // After the opening parenthesis, up to and including the closing one
+    fn param_list(&mut self) -> Result<Vec<SignatureParam>, ParseError> {
+        let mut params = Vec::new();
+        self.skip_whitespace();
+        if self.eat(')') {
+            return Ok(params);
+        }
+        loop {
+            params.push(self.param()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(')') => {
+                    self.position += 1;
+                    return Ok(params);
+                }
+                Some(c) => return Err(self.error(format!("expected `,` or `)`, found `{}`", c))),
+                None => return Err(self.error("expected `,` or `)`, found end of input")),
+            }
+        }
+    }
+
+    // A type followed by `indexed`, a data location and / or a name
+    fn param(&mut self) -> Result<SignatureParam, ParseError> {
+        let (kind, abi_type, components) = self.param_type()?;
+        let mut param = SignatureParam { name: None, kind, indexed: false, abi_type, components };
+        loop {
+            self.skip_whitespace();
+            let word_start = self.position;
+            let word = self.identifier();
+            match word.as_str() {
+                "" => return Ok(param),
+                "indexed" if param.name.is_none() => param.indexed = true,
+                "payable" if param.kind == ParamType::Address && param.name.is_none() => {}
+                w if LOCATIONS.contains(&w) && param.name.is_none() => {}
+                _ if param.name.is_none() => param.name = Some(word),
+                _ => {
+                    self.position = word_start;
+                    return Err(self.error(format!("unexpected `{}` after parameter name", word)));
+                }
+            }
+        }
+    }
+
+    // Also returns the declared type when it differs from the encoding, and the names of tuple
+    // components, for tuples and arrays of them
+    fn param_type(&mut self) -> Result<(ParamType, Option<String>, Vec<ParamLabel>), ParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let (mut kind, mut abi_type, components) = if self.eat('(') {
+            self.tuple()?
+        } else {
+            let word = self.identifier();
+            match word.as_str() {
+                "" => {
+                    return Err(match self.peek() {
+                        Some(c) => self.error(format!("expected a type, found `{}`", c)),
+                        None => self.error("expected a type, found end of input"),
+                    })
+                }
+                "tuple" if self.peek_after_whitespace() == Some('(') => {
+                    self.skip_whitespace();
+                    self.position += 1;
+                    self.tuple()?
+                }
+                _ => {
+                    let (kind, abi_type) = elementary_type(&word).map_err(|message| self.error_at(start, message))?;
+                    (kind, abi_type, Vec::new())
+                }
+            }
+        };
+
+        // Array suffixes, innermost first: "uint256[2][]" is a dynamic array of uint256[2]
+        while self.peek_after_whitespace() == Some('[') {
+            self.skip_whitespace();
+            self.position += 1;
+            self.skip_whitespace();
+            let size_start = self.position;
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            self.skip_whitespace();
+            self.expect(']')?;
+            kind = match digits {
+                "" => ParamType::Array(Box::new(kind)),
+                _ => match digits.parse::<usize>() {
+                    Ok(size) if size > 0 => ParamType::FixedArray(Box::new(kind), size),
+                    _ => return Err(self.error_at(size_start, format!("invalid array size {}", digits))),
+                },
+            };
+            abi_type = abi_type.map(|t| format!("{}[{}]", t, digits));
+        }
+        Ok((kind, abi_type, components))
+    }
+
+    fn tuple(&mut self) -> Result<(ParamType, Option<String>, Vec<ParamLabel>), ParseError> {
+        let params = self.param_list()?;
+        let labels = params.iter().map(SignatureParam::label).collect();
+        let abi_type = params
+            .iter()
+            .any(|p| p.abi_type.is_some())
+            .then(|| format!("({})", params.iter().map(SignatureParam::type_name).collect::<Vec<_>>().join(",")));
+        Ok((ParamType::Tuple(params.into_iter().map(|p| p.kind).collect()), abi_type, labels))
+    }
+
+    // Empty when the next character cannot start an identifier
+    fn identifier(&mut self) -> String {
+        if !self.peek().is_some_and(is_identifier_start) {
+            return String::new();
+        }
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$').to_string()
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += self.peek().map_or(0, char::len_utf8);
+        }
+        &self.input[start..self.position]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn peek_after_whitespace(&self) -> Option<char> {
+        self.input[self.position..].chars().find(|c| !c.is_whitespace())
+    }
+
+    fn at_end(&self) -> bool {
+        self.position == self.input.len()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        Err(match self.peek() {
+            Some(c) => self.error(format!("expected `{}`, found `{}`", expected, c)),
+            None => self.error(format!("expected `{}`, found end of input", expected)),
+        })
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Into<String>) -> ParseError {
+        ParseError { message: message.into(), position, input: self.input.to_string() }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$'
+}
+
+// Fixed-point numbers and function pointers have no ParamType, so they come back as the integer or
+// bytes24 they are encoded as, together with their canonical name
+fn elementary_type(word: &str) -> Result<(ParamType, Option<String>), String> {
+    let size = |prefix: &str| word[prefix.len()..].parse::<usize>().ok();
+    let kind = match word {
+        "function" => return Ok((ParamType::FixedBytes(24), Some(word.to_string()))),
+        "fixed" => return Ok((ParamType::Int(128), Some("fixed128x18".to_string()))),
+        "ufixed" => return Ok((ParamType::Uint(128), Some("ufixed128x18".to_string()))),
+        w if w.starts_with("fixed") || w.starts_with("ufixed") => return fixed_type(w),
+        "address" => Ok(ParamType::Address),
+        "bool" => Ok(ParamType::Bool),
+        "string" => Ok(ParamType::String),
+        "bytes" => Ok(ParamType::Bytes),
+        "byte" => Ok(ParamType::FixedBytes(1)),
+        "uint" => Ok(ParamType::Uint(256)),
+        "int" => Ok(ParamType::Int(256)),
+        w if w.starts_with("uint") => match size("uint") {
+            Some(bits) if bits > 0 && bits <= 256 && bits.is_multiple_of(8) => Ok(ParamType::Uint(bits)),
+            _ => Err(format!("invalid integer type `{}`, the size must be a multiple of 8 from 8 to 256", w)),
+        },
+        w if w.starts_with("int") => match size("int") {
+            Some(bits) if bits > 0 && bits <= 256 && bits.is_multiple_of(8) => Ok(ParamType::Int(bits)),
+            _ => Err(format!("invalid integer type `{}`, the size must be a multiple of 8 from 8 to 256", w)),
+        },
+        w if w.starts_with("bytes") => match size("bytes") {
+            Some(len) if len > 0 && len <= 32 => Ok(ParamType::FixedBytes(len)),
+            _ => Err(format!("invalid type `{}`, fixed bytes are bytes1 to bytes32", w)),
+        },
+        w => Err(format!("unknown type `{}`", w)),
+    };
+    kind.map(|kind| (kind, None))
+}
+
+// fixedMxN / ufixedMxN: M bits like intM, N decimals from 0 to 80
+fn fixed_type(word: &str) -> Result<(ParamType, Option<String>), String> {
+    let (signed, sizes) = match word.strip_prefix('u') {
+        Some(rest) => (false, &rest["fixed".len()..]),
+        None => (true, &word["fixed".len()..]),
+    };
+    let parsed = sizes.split_once('x').and_then(|(bits, decimals)| Some((bits.parse::<usize>().ok()?, decimals.parse::<usize>().ok()?)));
+    match parsed {
+        Some((bits, decimals)) if bits > 0 && bits <= 256 && bits.is_multiple_of(8) && decimals <= 80 => {
+            let kind = if signed { ParamType::Int(bits) } else { ParamType::Uint(bits) };
+            Ok((kind, Some(format!("{}fixed{}x{}", if signed { "" } else { "u" }, bits, decimals))))
+        }
+        _ => Err(format!(
+            "invalid fixed-point type `{}`, expected {}fixedMxN with M a multiple of 8 from 8 to 256 and N from 0 to 80",
+            word,
+            if signed { "" } else { "u" }
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(input: &str) -> String {
+        parse_signature(input).unwrap_or_else(|e| panic!("{}", e)).canonical()
+    }
+
+    fn error(input: &str) -> ParseError {
+        parse_signature(input).expect_err(input)
+    }
+
+    #[test]
+    fn canonical_forms() {
+        assert_eq!(canonical("transfer(address,uint256)"), "transfer(address,uint256)");
+        assert_eq!(canonical("  transfer( address to , uint amount ) "), "transfer(address,uint256)");
+        assert_eq!(canonical("function approve(address spender, uint256 value) external returns (bool);"), "approve(address,uint256)");
+        assert_eq!(canonical("event Transfer(address indexed from, address indexed to, uint256 value)"), "Transfer(address,address,uint256)");
+        assert_eq!(canonical("f(bytes calldata data, string memory s, address payable to)"), "f(bytes,string,address)");
+        assert_eq!(canonical("multicall((address target, bytes data)[] calls)"), "multicall((address,bytes)[])");
+        assert_eq!(canonical("f(tuple(uint256 a, bool b)[2][] x)"), "f((uint256,bool)[2][])");
+        assert_eq!(canonical("f(int,fixed,ufixed64x2[],function)"), "f(int256,fixed128x18,ufixed64x2[],function)");
+        assert_eq!(canonical("f()"), "f()");
+    }
+
+    #[test]
+    fn params_and_outputs() {
+        let signature = parse_signature("event Swap(address indexed sender, int256 amount)").unwrap();
+        assert!(signature.inputs[0].indexed && !signature.inputs[1].indexed);
+        assert_eq!(signature.inputs[1].name.as_deref(), Some("amount"));
+
+        let arrow = parse_signature("balanceOf(address) -> uint256").unwrap();
+        assert_eq!(arrow.outputs.unwrap().iter().map(|p| p.kind.clone()).collect::<Vec<_>>(), [ParamType::Uint(256)]);
+        let two = parse_signature("f() -> (uint256,bool)").unwrap();
+        assert_eq!(two.outputs.unwrap().len(), 2);
+        let array = parse_signature("f() -> (uint256,bool)[]").unwrap();
+        assert_eq!(array.outputs.unwrap()[0].kind.to_string(), "(uint256,bool)[]");
+
+        let fixed = parse_signature("f(ufixed128x18[2] prices)").unwrap();
+        assert_eq!(fixed.inputs[0].kind, ParamType::FixedArray(Box::new(ParamType::Uint(128)), 2));
+        assert_eq!(fixed.inputs[0].abi_type.as_deref(), Some("ufixed128x18[2]"));
+    }
+
+    #[test]
+    fn parse_types() {
+        assert_eq!(parse_type("uint8[]").unwrap(), ParamType::Array(Box::new(ParamType::Uint(8))));
+        assert_eq!(parse_type("(address,bytes32)").unwrap().to_string(), "(address,bytes32)");
+        assert_eq!(parse_type("function").unwrap(), ParamType::FixedBytes(24));
+        assert!(parse_type("uint256 x y").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let e = error("transfer(address,uint7)");
+        assert_eq!(e.position, 17);
+        assert!(e.message.contains("uint7"), "{}", e);
+        assert_eq!(error("f(uint256").message, "expected `,` or `)`, found end of input");
+        assert_eq!(error("f(uint256[0])").message, "invalid array size 0");
+        assert_eq!(error("(uint256)").message, "expected a name");
+        assert_eq!(error("f(,)").message, "expected a type, found `,`");
+        assert_eq!(error("f(uint256 a b)").message, "unexpected `b` after parameter name");
+        assert_eq!(error("f() view extra").message, "unexpected `extra`");
+        assert_eq!(error("f() x").position, 4);
+        assert!(error("f(bytes33)").message.contains("bytes33"));
+        assert!(error("f(fixed128x81)").message.contains("fixed128x81"));
+        assert!(error("f(ufixed7x2)").message.contains("ufixed7x2"));
+        assert_eq!(error("f(uint256))").message, "unexpected trailing input");
+    }
+}
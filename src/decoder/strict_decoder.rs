@@ -0,0 +1,352 @@
+use std::fmt;
+use ethabi::{ParamType, Token};
+use ethereum_types::{Address, U256};
+use serde::Serialize;
+use super::abi_guesser::is_dynamic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    // Non-zero bits an encoder would have cleared: high bytes of addresses and small ints, bools other
+    // than 0 / 1, the tail of bytesN, the padding after bytes and strings
+    DirtyPadding,
+    // An offset pointing back into data that was already read: the head, or an earlier dynamic value
+    OverlappingOffset,
+    // An offset leaving a gap after the previous value
+    NonCanonicalOffset,
+    TrailingBytes,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodingViolation {
+    pub kind: ViolationKind,
+    // Byte offset into the calldata (selector included) of the offending word
+    pub offset: usize,
+    // The value it belongs to, e.g. "amount", "calls[1].0" or "[2]" for unnamed params, empty for trailing bytes
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for EncodingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "byte {}: {}", self.offset, self.message),
+            path => write!(f, "{} at byte {}: {}", path, self.offset, self.message),
+        }
+    }
+}
+
+// Decodes like ethabi::decode, but accepts anything a lenient decoder would (dirty bits are kept as
+// they are, bools are true when non-zero) and reports every departure from the canonical encoding
+// solc produces. `names` label the top-level params, `base` is where `data` starts in the calldata.
+// Only fails when the data is too short for what its offsets and lengths claim.
+pub fn decode_strict(
+    types: &[ParamType],
+    names: &[String],
+    data: &[u8],
+    base: usize,
+) -> Result<(Vec<Token>, Vec<EncodingViolation>), String> {
+    let mut decoder = StrictDecoder { data, base, violations: Vec::new() };
+    let paths = (0..types.len()).map(|i| names.get(i).cloned().unwrap_or_else(|| format!("[{}]", i))).collect::<Vec<_>>();
+    let (tokens, end) = decoder.tuple(types, &paths, 0)?;
+    if end < data.len() {
+        decoder.violation(
+            ViolationKind::TrailingBytes,
+            end,
+            "",
+            format!("{} bytes after the end of the encoded arguments", data.len() - end),
+        );
+    }
+    Ok((tokens, decoder.violations))
+}
+
+struct StrictDecoder<'a> {
+    data: &'a [u8],
+    base: usize,
+    violations: Vec<EncodingViolation>,
+}
+
+impl StrictDecoder<'_> {
+    // A head of one slot per member followed by the dynamic members' data, offsets relative to `start`.
+    // Also returns where the encoding ends.
+    fn tuple(&mut self, types: &[ParamType], paths: &[String], start: usize) -> Result<(Vec<Token>, usize), String> {
+        let head_len = types.iter().map(head_size).sum::<usize>();
+        // Where the next dynamic value starts in a canonical encoding
+        let mut tail = start + head_len;
+        let mut position = start;
+        let mut tokens = Vec::with_capacity(types.len());
+
+        for (kind, path) in types.iter().zip(paths) {
+            if is_dynamic(kind) {
+                let word = self.word(position, path)?;
+                if word > U256::from(self.data.len() - start) {
+                    return Err(format!("{} at byte {}: offset {} points past the end of the data", path, self.base + position, word));
+                }
+                let target = start + word.as_usize();
+                if target < tail {
+                    let message = match target < start + head_len {
+                        true => format!("offset {} points into the head, which ends at {}", word, head_len),
+                        false => format!("offset {} points back into the previous value, which ends at {}", word, tail - start),
+                    };
+                    self.violation(ViolationKind::OverlappingOffset, position, path, message);
+                } else if target > tail {
+                    let message = format!("offset {} leaves a gap of {} bytes, expected {}", word, target - tail, tail - start);
+                    self.violation(ViolationKind::NonCanonicalOffset, position, path, message);
+                }
+                let (token, end) = self.dynamic(kind, path, target)?;
+                tokens.push(token);
+                tail = tail.max(end);
+            } else {
+                tokens.push(self.fixed(kind, path, position)?);
+            }
+            position += head_size(kind);
+        }
+        Ok((tokens, tail))
+    }
+
+    // A dynamic value at `start`, and where it ends
+    fn dynamic(&mut self, kind: &ParamType, path: &str, start: usize) -> Result<(Token, usize), String> {
+        match kind {
+            ParamType::Bytes | ParamType::String => {
+                let length = self.length(start, path, 1)?;
+                let content = start + 32;
+                let bytes = self.data[content..content + length].to_vec();
+                let padded_end = content + length.div_ceil(32) * 32;
+                let padding = &self.data[content + length..padded_end.min(self.data.len())];
+                if padded_end > self.data.len() {
+                    let message = format!("missing {} padding bytes after the {} bytes of content", padded_end - self.data.len(), length);
+                    self.violation(ViolationKind::DirtyPadding, content + length, path, message);
+                } else if padding.iter().any(|b| *b != 0) {
+                    let message = format!("non-zero padding 0x{} after the {} bytes of content", hex::encode(padding), length);
+                    self.violation(ViolationKind::DirtyPadding, content + length, path, message);
+                }
+                let token = match kind {
+                    ParamType::String => Token::String(String::from_utf8_lossy(&bytes).into()),
+                    _ => Token::Bytes(bytes),
+                };
+                Ok((token, padded_end))
+            }
+            ParamType::Array(inner) => {
+                // Every element takes at least one word
+                let length = self.length(start, path, 32)?;
+                let kinds = vec![(**inner).clone(); length];
+                let paths = (0..length).map(|i| format!("{}[{}]", path, i)).collect::<Vec<_>>();
+                let (tokens, end) = self.tuple(&kinds, &paths, start + 32)?;
+                Ok((Token::Array(tokens), end))
+            }
+            ParamType::FixedArray(inner, length) => {
+                let kinds = vec![(**inner).clone(); *length];
+                let paths = (0..*length).map(|i| format!("{}[{}]", path, i)).collect::<Vec<_>>();
+                let (tokens, end) = self.tuple(&kinds, &paths, start)?;
+                Ok((Token::FixedArray(tokens), end))
+            }
+            ParamType::Tuple(kinds) => {
+                let paths = (0..kinds.len()).map(|i| format!("{}.{}", path, i)).collect::<Vec<_>>();
+                let (tokens, end) = self.tuple(kinds, &paths, start)?;
+                Ok((Token::Tuple(tokens), end))
+            }
+            _ => unreachable!("{} is not dynamic", kind),
+        }
+    }
+
+    // A static value encoded in place at `position`
+    fn fixed(&mut self, kind: &ParamType, path: &str, position: usize) -> Result<Token, String> {
+        match kind {
+            ParamType::FixedArray(inner, length) => {
+                let tokens = (0..*length)
+                    .map(|i| self.fixed(inner, &format!("{}[{}]", path, i), position + i * head_size(inner)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(Token::FixedArray(tokens));
+            }
+            ParamType::Tuple(kinds) => {
+                let mut member = position;
+                let mut tokens = Vec::with_capacity(kinds.len());
+                for (i, kind) in kinds.iter().enumerate() {
+                    tokens.push(self.fixed(kind, &format!("{}.{}", path, i), member)?);
+                    member += head_size(kind);
+                }
+                return Ok(Token::Tuple(tokens));
+            }
+            _ => {}
+        }
+
+        let word = self.word(position, path)?;
+        let bytes = &self.data[position..position + 32];
+        let dirty = match kind {
+            ParamType::Address if bytes[..12].iter().any(|b| *b != 0) => {
+                Some(format!("address with dirty high bytes 0x{}", hex::encode(&bytes[..12])))
+            }
+            ParamType::Bool if word > U256::one() => Some(format!("bool encoded as {}, not 0 or 1", word)),
+            ParamType::Uint(bits) if *bits < 256 && !(word >> *bits).is_zero() => {
+                Some(format!("uint{} with dirty high bits, 0x{:x}", bits, word))
+            }
+            // The bits above the value have to repeat its sign bit
+            ParamType::Int(bits) if *bits < 256 => {
+                let high = word >> (*bits - 1);
+                let sign_extended = high.is_zero() || high == U256::MAX >> (*bits - 1);
+                (!sign_extended).then(|| format!("int{} that is not sign-extended, 0x{:x}", bits, word))
+            }
+            ParamType::FixedBytes(len) if bytes[*len..].iter().any(|b| *b != 0) => {
+                Some(format!("bytes{} with dirty low bytes 0x{}", len, hex::encode(&bytes[*len..])))
+            }
+            _ => None,
+        };
+        if let Some(message) = dirty {
+            self.violation(ViolationKind::DirtyPadding, position, path, message);
+        }
+
+        Ok(match kind {
+            ParamType::Address => Token::Address(Address::from_slice(&bytes[12..])),
+            ParamType::Bool => Token::Bool(!word.is_zero()),
+            ParamType::Uint(_) => Token::Uint(word),
+            ParamType::Int(_) => Token::Int(word),
+            ParamType::FixedBytes(len) => Token::FixedBytes(bytes[..*len].to_vec()),
+            _ => unreachable!("{} is not static", kind),
+        })
+    }
+
+    fn word(&self, position: usize, path: &str) -> Result<U256, String> {
+        match self.data.get(position..position + 32) {
+            Some(word) => Ok(U256::from_big_endian(word)),
+            None => Err(format!("{} at byte {}: the data ends at byte {}", path, self.base + position, self.base + self.data.len())),
+        }
+    }
+
+    // The length word of a bytes value or array, checked against what is left when each item takes `item_size` bytes
+    fn length(&self, position: usize, path: &str, item_size: usize) -> Result<usize, String> {
+        let length = self.word(position, path)?;
+        let available = (self.data.len() - position - 32) / item_size;
+        if length > U256::from(available) {
+            return Err(format!("{} at byte {}: length {} runs past the end of the data", path, self.base + position, length));
+        }
+        Ok(length.as_usize())
+    }
+
+    fn violation(&mut self, kind: ViolationKind, position: usize, path: &str, message: String) {
+        self.violations.push(EncodingViolation { kind, offset: self.base + position, path: path.to_string(), message });
+    }
+}
+
+// Bytes a value takes in its enclosing head: an offset for dynamic values, the value itself otherwise
+fn head_size(kind: &ParamType) -> usize {
+    match kind {
+        _ if is_dynamic(kind) => 32,
+        ParamType::FixedArray(inner, length) => head_size(inner) * length,
+        ParamType::Tuple(kinds) => kinds.iter().map(head_size).sum(),
+        _ => 32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: u64) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        U256::from(value).to_big_endian(&mut word);
+        word
+    }
+
+    fn kinds(violations: &[EncodingViolation]) -> Vec<ViolationKind> {
+        violations.iter().map(|v| v.kind).collect()
+    }
+
+    #[test]
+    fn canonical_encoding_has_no_violations() {
+        let types = [ParamType::Address, ParamType::Bytes, ParamType::Array(Box::new(ParamType::String)), ParamType::Int(8)];
+        let tokens = vec![
+            Token::Address(Address::repeat_byte(0x11)),
+            Token::Bytes(vec![1, 2, 3]),
+            Token::Array(vec![Token::String("a".into()), Token::String("".into())]),
+            Token::Int(U256::MAX),
+        ];
+        let (decoded, violations) = decode_strict(&types, &[], &ethabi::encode(&tokens), 4).unwrap();
+        assert_eq!(decoded, tokens);
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn dirty_padding() {
+        let types = [ParamType::Address, ParamType::Bool, ParamType::Uint(8), ParamType::Int(16), ParamType::FixedBytes(2)];
+        let mut data = ethabi::encode(&[
+            Token::Address(Address::zero()),
+            Token::Bool(true),
+            Token::Uint(1.into()),
+            Token::Int(1.into()),
+            Token::FixedBytes(vec![1, 2]),
+        ]);
+        data[0] = 0xff;
+        data[32 + 31] = 2;
+        data[64 + 30] = 1;
+        data[96] = 0x80;
+        data[128 + 31] = 1;
+        let names = ["owner".to_string()];
+        let (tokens, violations) = decode_strict(&types, &names, &data, 4).unwrap();
+        assert_eq!(kinds(&violations), [ViolationKind::DirtyPadding; 5]);
+        assert_eq!(violations.iter().map(|v| v.offset).collect::<Vec<_>>(), [4, 36, 68, 100, 132]);
+        assert_eq!(violations[0].path, "owner");
+        assert_eq!(violations[1].path, "[1]");
+        // Decoded leniently
+        assert_eq!(tokens[1], Token::Bool(true));
+        assert_eq!(tokens[2], Token::Uint(0x101.into()));
+
+        let mut data = ethabi::encode(&[Token::Bytes(vec![0xaa])]);
+        data[64 + 1] = 1;
+        let (_, violations) = decode_strict(&[ParamType::Bytes], &[], &data, 0).unwrap();
+        assert_eq!(kinds(&violations), [ViolationKind::DirtyPadding]);
+        assert_eq!(violations[0].offset, 65);
+
+        // Padding cut off at the end of the data
+        let (tokens, violations) = decode_strict(&[ParamType::Bytes], &[], &data[..66], 0).unwrap();
+        assert_eq!(tokens, [Token::Bytes(vec![0xaa])]);
+        assert!(violations[0].message.starts_with("missing 30 padding bytes"), "{}", violations[0]);
+    }
+
+    #[test]
+    fn overlapping_and_non_canonical_offsets() {
+        // Both strings share one value
+        let mut content = [0u8; 32];
+        content[0] = b'a';
+        let data = [word(64), word(64), word(1), content].concat();
+        let (tokens, violations) = decode_strict(&[ParamType::String, ParamType::String], &[], &data, 0).unwrap();
+        assert_eq!(tokens, [Token::String("a".into()), Token::String("a".into())]);
+        assert_eq!(kinds(&violations), [ViolationKind::OverlappingOffset]);
+        assert_eq!(violations[0].offset, 32);
+        assert!(violations[0].message.contains("previous value"), "{}", violations[0]);
+
+        // Pointing into the head
+        let data = [word(0), word(0)].concat();
+        let (_, violations) = decode_strict(&[ParamType::Bytes, ParamType::Uint(256)], &[], &data, 0).unwrap();
+        assert_eq!(kinds(&violations), [ViolationKind::OverlappingOffset]);
+        assert!(violations[0].message.contains("into the head"), "{}", violations[0]);
+
+        // A gap of one word before the array
+        let data = [word(64), word(0), word(1), word(7)].concat();
+        let (tokens, violations) = decode_strict(&[ParamType::Array(Box::new(ParamType::Uint(256)))], &[], &data, 4).unwrap();
+        assert_eq!(tokens, [Token::Array(vec![Token::Uint(7.into())])]);
+        assert_eq!(kinds(&violations), [ViolationKind::NonCanonicalOffset]);
+        assert_eq!(violations[0].to_string(), "[0] at byte 4: offset 64 leaves a gap of 32 bytes, expected 32");
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut data = ethabi::encode(&[Token::Uint(1.into())]);
+        data.extend([0u8; 5]);
+        let (_, violations) = decode_strict(&[ParamType::Uint(256)], &[], &data, 4).unwrap();
+        assert_eq!(kinds(&violations), [ViolationKind::TrailingBytes]);
+        assert_eq!(violations[0].to_string(), "byte 36: 5 bytes after the end of the encoded arguments");
+    }
+
+    #[test]
+    fn fails_when_the_data_is_too_short() {
+        let error = decode_strict(&[ParamType::Uint(256), ParamType::Address], &[], &word(1), 4).unwrap_err();
+        assert_eq!(error, "[1] at byte 36: the data ends at byte 36");
+
+        let error = decode_strict(&[ParamType::Bytes], &[], &word(64), 0).unwrap_err();
+        assert_eq!(error, "[0] at byte 0: offset 64 points past the end of the data");
+
+        let data = [word(32), word(1000)].concat();
+        let error = decode_strict(&[ParamType::Bytes], &[], &data, 0).unwrap_err();
+        assert_eq!(error, "[0] at byte 32: length 1000 runs past the end of the data");
+    }
+}
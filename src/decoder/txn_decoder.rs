@@ -1,16 +1,25 @@
 use ethereum_types::{H160, U256, H256};
-use rlp::{Rlp, Decodable, DecoderError};
-use crate::utils;
+use rlp::{Rlp, RlpStream, Decodable, DecoderError, Encodable};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use super::utils;
 
-#[derive(Debug)]
-enum TransactionType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionType {
     Legacy,
     EIP2930,
     EIP1559,
+    EIP4844,
+    EIP7702,
+    Deposit,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct AccessListItem(H160, Vec<H256>);
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessListItem(pub H160, pub Vec<H256>);
 
 impl Decodable for AccessListItem {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
@@ -24,8 +33,15 @@ impl Decodable for AccessListItem {
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+impl Encodable for AccessListItem {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.append(&self.0);
+        s.append_list(&self.1);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct AccessList(pub Vec<AccessListItem>);
 
 impl Decodable for AccessList {
@@ -35,28 +51,211 @@ impl Decodable for AccessList {
     }
 }
 
+impl Encodable for AccessList {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_list(&self.0);
+    }
+}
+
+// One EIP-7702 delegation: the authority signs (chain_id, address, nonce) to set its code to `address`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationListItem {
+    pub chain_id: U256,
+    pub address: H160,
+    pub nonce: u64,
+    pub y_parity: U256,
+    pub r: U256,
+    pub s: U256,
+    // Recovered from the tuple signature, None when it does not recover to a key
+    pub authority: Option<H160>,
+}
+
+impl Decodable for AuthorizationListItem {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let y_parity: U256 = rlp.val_at(3)?;
+        let r: U256 = rlp.val_at(4)?;
+        let s: U256 = rlp.val_at(5)?;
+
+        // The authority signed keccak256(0x05 || rlp([chain_id, address, nonce]))
+        let signing_hash = H256(utils::keccak256(&signing_payload(Some(0x05), rlp, 3, None)?));
+        let authority = if y_parity.bits() <= 64 {
+            recover_sender(&signing_hash, y_parity.low_u64(), r, s).ok()
+        } else {
+            None
+        };
+
+        Ok(AuthorizationListItem {
+            chain_id: rlp.val_at(0)?,
+            address: rlp.val_at(1)?,
+            nonce: rlp.val_at(2)?,
+            y_parity,
+            r,
+            s,
+            authority,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationList(pub Vec<AuthorizationListItem>);
+
+impl Decodable for AuthorizationList {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let vec: Vec<AuthorizationListItem> = rlp.as_list()?;
+        Ok(AuthorizationList(vec))
+    }
+}
+
+// Blobs, KZG commitments and proofs carried by the network form of a blob transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobSidecar {
+    #[serde(with = "utils::hex_bytes_list")]
+    pub blobs: Vec<Vec<u8>>,
+    #[serde(with = "utils::hex_bytes_list")]
+    pub commitments: Vec<Vec<u8>>,
+    #[serde(with = "utils::hex_bytes_list")]
+    pub proofs: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub transaction_type: TransactionType,
+    pub chain_id: Option<u64>,
+    // None for deposit transactions, which carry neither a nonce nor a signature
+    pub nonce: Option<U256>,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas_limit: U256,
+    pub to: Option<H160>,
+    pub value: U256,
+    #[serde(with = "utils::hex_bytes")]
+    pub data: Vec<u8>,
+    pub access_list: Option<AccessList>,
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blob_versioned_hashes: Option<Vec<H256>>,
+    // Only present when the transaction was submitted in its network wrapper form
+    pub blob_sidecar: Option<BlobSidecar>,
+    pub authorization_list: Option<AuthorizationList>,
+    pub v: Option<U256>,
+    pub r: Option<U256>,
+    pub s: Option<U256>,
+    // Recovered from the signature, None when the signature does not recover to a key
+    pub from: Option<H160>,
+    // keccak256 of the full typed envelope, the hash block explorers index
+    pub hash: H256,
+    // keccak256 of the unsigned payload that was signed, None for unsigned deposit transactions
+    pub signing_hash: Option<H256>,
+    // OP-Stack deposit fields
+    pub source_hash: Option<H256>,
+    pub mint: Option<U256>,
+    pub is_system_tx: Option<bool>,
+}
+
+// Names the RLP field that failed so callers can report it precisely
 #[derive(Debug)]
-#[allow(dead_code)]
-struct Transaction {
-    transaction_type: TransactionType,
-    chain_id: Option<u64>,
-    nonce: U256,
-    gas_price: Option<U256>,
-    max_priority_fee_per_gas: Option<U256>,
-    max_fee_per_gas: Option<U256>,
-    gas_limit: U256,
-    to: Option<H160>,
-    value: U256,
-    data: Vec<u8>,
-    access_list: Option<AccessList>,
-    v: U256,
-    r: U256,
-    s: U256,
-}
-
-fn decode_transaction(hex_tx: &str) -> Result<Transaction, Box<dyn std::error::Error>> {
+pub struct FieldDecodeError {
+    pub field: &'static str,
+    pub error: DecoderError,
+}
+
+impl fmt::Display for FieldDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to decode {}: {:?}", self.field, self.error)
+    }
+}
+
+impl std::error::Error for FieldDecodeError {}
+
+fn field_at<T: Decodable>(rlp: &Rlp, index: usize, field: &'static str) -> Result<T, FieldDecodeError> {
+    rlp.val_at(index).map_err(|error| FieldDecodeError { field, error })
+}
+
+// Rlp::item_count silently stops at a truncated item, so check the list header against the input first
+fn list_item_count(rlp: &Rlp) -> Result<usize, FieldDecodeError> {
+    let envelope_err = |error| FieldDecodeError { field: "transaction envelope", error };
+    let info = rlp.payload_info().map_err(envelope_err)?;
+    let expected = info.header_len + info.value_len;
+    if rlp.as_raw().len() < expected {
+        return Err(envelope_err(DecoderError::RlpIsTooShort));
+    }
+    if rlp.as_raw().len() > expected {
+        return Err(envelope_err(DecoderError::RlpIsTooBig));
+    }
+    rlp.item_count().map_err(envelope_err)
+}
+
+fn list_field_at<T: Decodable>(rlp: &Rlp, index: usize, field: &'static str) -> Result<Vec<T>, FieldDecodeError> {
+    rlp.list_at(index).map_err(|error| FieldDecodeError { field, error })
+}
+
+fn optional_address_at(rlp: &Rlp, index: usize) -> Result<Option<H160>, FieldDecodeError> {
+    let item = rlp.at(index).map_err(|error| FieldDecodeError { field: "to", error })?;
+    if item.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(field_at(rlp, index, "to")?))
+    }
+}
+
+// Re-wraps the unsigned fields exactly as they were encoded, prefixed with the type byte for typed transactions
+fn signing_payload(tx_type: Option<u8>, rlp: &Rlp, unsigned_items: usize, eip155_chain_id: Option<u64>) -> Result<Vec<u8>, DecoderError> {
+    let mut stream = RlpStream::new_list(unsigned_items + if eip155_chain_id.is_some() { 3 } else { 0 });
+    for i in 0..unsigned_items {
+        stream.append_raw(rlp.at(i)?.as_raw(), 1);
+    }
+    if let Some(chain_id) = eip155_chain_id {
+        stream.append(&chain_id);
+        stream.append_empty_data();
+        stream.append_empty_data();
+    }
+
+    let mut payload = Vec::new();
+    payload.extend(tx_type);
+    payload.extend_from_slice(&stream.out());
+    Ok(payload)
+}
+
+fn recover_sender(signing_hash: &H256, recovery_id: u64, r: U256, s: U256) -> Result<H160, Box<dyn std::error::Error>> {
+    if recovery_id > 1 {
+        return Err(format!("Invalid signature recovery id: {}", recovery_id).into());
+    }
+
+    let mut compact = [0u8; 64];
+    r.to_big_endian(&mut compact[..32]);
+    s.to_big_endian(&mut compact[32..]);
+
+    let signature = RecoverableSignature::from_compact(&compact, RecoveryId::from_i32(recovery_id as i32)?)?;
+    let message = Message::from_slice(signing_hash.as_bytes())?;
+    let public_key = Secp256k1::verification_only().recover_ecdsa(&message, &signature)?;
+
+    // The address is the last 20 bytes of the hash of the uncompressed key without its 0x04 prefix
+    let hash = utils::keccak256(&public_key.serialize_uncompressed()[1..]);
+    Ok(H160::from_slice(&hash[12..]))
+}
+
+// Legacy v is 27/28 before EIP-155 and chain_id * 2 + 35/36 after it
+fn legacy_chain_id_and_parity(v: U256) -> Option<(Option<u64>, u64)> {
+    if v.bits() > 64 {
+        return None;
+    }
+    match v.low_u64() {
+        v @ (27 | 28) => Some((None, v - 27)),
+        v if v >= 35 => Some((Some((v - 35) / 2), (v - 35) % 2)),
+        _ => None,
+    }
+}
+
+pub fn decode_transaction(hex_tx: &str) -> Result<Transaction, Box<dyn std::error::Error>> {
     let raw_tx: Vec<u8> = hex::decode(utils::remove_0x_prefix(hex_tx))?;
-    
+    decode_transaction_bytes(&raw_tx)
+}
+
+pub fn decode_transaction_bytes(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
     if raw_tx.is_empty() {
         return Err("Empty transaction data".into());
     }
@@ -64,128 +263,522 @@ fn decode_transaction(hex_tx: &str) -> Result<Transaction, Box<dyn std::error::E
     match raw_tx[0] {
         0x01 => decode_eip2930_transaction(&raw_tx[1..]),
         0x02 => decode_eip1559_transaction(&raw_tx[1..]),
-        _ if raw_tx[0] > 0x7f => decode_legacy_transaction(raw_tx.as_slice()),
+        0x03 => decode_eip4844_transaction(&raw_tx[1..]),
+        0x04 => decode_eip7702_transaction(&raw_tx[1..]),
+        0x7e => decode_deposit_transaction(&raw_tx[1..]),
+        _ if raw_tx[0] > 0x7f => decode_legacy_transaction(raw_tx),
         _ => Err(format!("Unsupported transaction type: {:02x}", raw_tx[0]).into()),
     }
 }
 
 fn decode_legacy_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
-    println!("Decoding legacy transaction");
     let rlp = Rlp::new(raw_tx);
     
-    let item_count = rlp.item_count().map_err(|e| format!("Failed to get item count: {:?}", e))?;
+    let item_count = list_item_count(&rlp)?;
 
     if item_count != 9 {
         return Err(format!("Expected 9 items for legacy transaction, got {}", item_count).into());
     }
 
-    let to: Option<H160> = if rlp.at(3)?.is_empty() {
-        None
-    } else {
-        Some(rlp.val_at(3).map_err(|e| format!("Failed to decode to: {:?}", e))?)
+    let to = optional_address_at(&rlp, 3)?;
+    let v: U256 = field_at(&rlp, 6, "v")?;
+    let r: U256 = field_at(&rlp, 7, "r")?;
+    let s: U256 = field_at(&rlp, 8, "s")?;
+
+    let hash = H256(utils::keccak256(raw_tx));
+
+    // An unrecognised v leaves the sender unknown, the pre-EIP-155 payload is still hashed for reference
+    let (chain_id, parity) = match legacy_chain_id_and_parity(v) {
+        Some((chain_id, parity)) => (chain_id, Some(parity)),
+        None => (None, None),
     };
+    let signing_hash = H256(utils::keccak256(&signing_payload(None, &rlp, 6, chain_id)?));
+    let from = parity.and_then(|parity| recover_sender(&signing_hash, parity, r, s).ok());
 
     Ok(Transaction {
         transaction_type: TransactionType::Legacy,
-        chain_id: None,
-        nonce: rlp.val_at(0).map_err(|e| format!("Failed to decode nonce: {:?}", e))?,
-        gas_price: Some(rlp.val_at(1).map_err(|e| format!("Failed to decode gas_price: {:?}", e))?),
+        chain_id,
+        nonce: Some(field_at(&rlp, 0, "nonce")?),
+        gas_price: Some(field_at(&rlp, 1, "gas_price")?),
         max_priority_fee_per_gas: None,
         max_fee_per_gas: None,
-        gas_limit: rlp.val_at(2).map_err(|e| format!("Failed to decode gas_limit: {:?}", e))?,
+        gas_limit: field_at(&rlp, 2, "gas_limit")?,
         to,
-        value: rlp.val_at(4).map_err(|e| format!("Failed to decode value: {:?}", e))?,
-        data: rlp.val_at(5).map_err(|e| format!("Failed to decode data: {:?}", e))?,
+        value: field_at(&rlp, 4, "value")?,
+        data: field_at(&rlp, 5, "data")?,
         access_list: None,
-        v: rlp.val_at(6).map_err(|e| format!("Failed to decode v: {:?}", e))?,
-        r: rlp.val_at(7).map_err(|e| format!("Failed to decode r: {:?}", e))?,
-        s: rlp.val_at(8).map_err(|e| format!("Failed to decode s: {:?}", e))?,
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: None,
+        blob_sidecar: None,
+        authorization_list: None,
+        v: Some(v),
+        r: Some(r),
+        s: Some(s),
+        from,
+        hash,
+        signing_hash: Some(signing_hash),
+        source_hash: None,
+        mint: None,
+        is_system_tx: None,
     })
 }
 
 fn decode_eip2930_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
-    println!("Decoding EIP-2930 transaction");
     let rlp = Rlp::new(raw_tx);
     
-    let item_count = rlp.item_count().map_err(|e| format!("Failed to get item count: {:?}", e))?;
+    let item_count = list_item_count(&rlp)?;
 
     if item_count != 11 {
         return Err(format!("Expected 11 items for EIP-2930 transaction, got {}", item_count).into());
     }
 
-    let to: Option<H160> = if rlp.at(4)?.is_empty() {
-        None
-    } else {
-        Some(rlp.val_at(4).map_err(|e| format!("Failed to decode to: {:?}", e))?)
-    };
+    let to = optional_address_at(&rlp, 4)?;
+
+    let v: U256 = field_at(&rlp, 8, "v")?;
+    let r: U256 = field_at(&rlp, 9, "r")?;
+    let s: U256 = field_at(&rlp, 10, "s")?;
+    let hash = H256(utils::keccak256(&[&[0x01], raw_tx].concat()));
+    let signing_hash = H256(utils::keccak256(&signing_payload(Some(0x01), &rlp, 8, None)?));
+    let from = if v.bits() <= 64 { recover_sender(&signing_hash, v.low_u64(), r, s).ok() } else { None };
 
     Ok(Transaction {
         transaction_type: TransactionType::EIP2930,
-        chain_id: Some(rlp.val_at(0).map_err(|e| format!("Failed to decode chain_id: {:?}", e))?),
-        nonce: rlp.val_at(1).map_err(|e| format!("Failed to decode nonce: {:?}", e))?,
-        gas_price: Some(rlp.val_at(2).map_err(|e| format!("Failed to decode gas_price: {:?}", e))?),
+        chain_id: Some(field_at(&rlp, 0, "chain_id")?),
+        nonce: Some(field_at(&rlp, 1, "nonce")?),
+        gas_price: Some(field_at(&rlp, 2, "gas_price")?),
         max_priority_fee_per_gas: None,
         max_fee_per_gas: None,
-        gas_limit: rlp.val_at(3).map_err(|e| format!("Failed to decode gas_limit: {:?}", e))?,
+        gas_limit: field_at(&rlp, 3, "gas_limit")?,
         to,
-        value: rlp.val_at(5).map_err(|e| format!("Failed to decode value: {:?}", e))?,
-        data: rlp.val_at(6).map_err(|e| format!("Failed to decode data: {:?}", e))?,
-        access_list: Some(rlp.val_at(7).map_err(|e| format!("Failed to decode access_list: {:?}", e))?),
-        v: rlp.val_at(8).map_err(|e| format!("Failed to decode v: {:?}", e))?,
-        r: rlp.val_at(9).map_err(|e| format!("Failed to decode r: {:?}", e))?,
-        s: rlp.val_at(10).map_err(|e| format!("Failed to decode s: {:?}", e))?,
+        value: field_at(&rlp, 5, "value")?,
+        data: field_at(&rlp, 6, "data")?,
+        access_list: Some(field_at(&rlp, 7, "access_list")?),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: None,
+        blob_sidecar: None,
+        authorization_list: None,
+        v: Some(v),
+        r: Some(r),
+        s: Some(s),
+        from,
+        hash,
+        signing_hash: Some(signing_hash),
+        source_hash: None,
+        mint: None,
+        is_system_tx: None,
     })
 }
 
 fn decode_eip1559_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
-    println!("Decoding EIP-1559 transaction");
     let rlp = Rlp::new(raw_tx);
     
-    let item_count = rlp.item_count().map_err(|e| format!("Failed to get item count: {:?}", e))?;
+    let item_count = list_item_count(&rlp)?;
 
     if item_count != 12 {
         return Err(format!("Expected 12 items for EIP-1559 transaction, got {}", item_count).into());
     }
 
-    let to: Option<H160> = if rlp.at(5)?.is_empty() {
-        None
-    } else {
-        Some(rlp.val_at(5).map_err(|e| format!("Failed to decode to: {:?}", e))?)
-    };
+    let to = optional_address_at(&rlp, 5)?;
+
+    let v: U256 = field_at(&rlp, 9, "v")?;
+    let r: U256 = field_at(&rlp, 10, "r")?;
+    let s: U256 = field_at(&rlp, 11, "s")?;
+    let hash = H256(utils::keccak256(&[&[0x02], raw_tx].concat()));
+    let signing_hash = H256(utils::keccak256(&signing_payload(Some(0x02), &rlp, 9, None)?));
+    let from = if v.bits() <= 64 { recover_sender(&signing_hash, v.low_u64(), r, s).ok() } else { None };
 
     Ok(Transaction {
         transaction_type: TransactionType::EIP1559,
-        chain_id: Some(rlp.val_at(0).map_err(|e| format!("Failed to decode chain_id: {:?}", e))?),
-        nonce: rlp.val_at(1).map_err(|e| format!("Failed to decode nonce: {:?}", e))?,
+        chain_id: Some(field_at(&rlp, 0, "chain_id")?),
+        nonce: Some(field_at(&rlp, 1, "nonce")?),
+        gas_price: None,
+        max_priority_fee_per_gas: Some(field_at(&rlp, 2, "max_priority_fee_per_gas")?),
+        max_fee_per_gas: Some(field_at(&rlp, 3, "max_fee_per_gas")?),
+        gas_limit: field_at(&rlp, 4, "gas_limit")?,
+        to,
+        value: field_at(&rlp, 6, "value")?,
+        data: field_at(&rlp, 7, "data")?,
+        access_list: Some(field_at(&rlp, 8, "access_list")?),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: None,
+        blob_sidecar: None,
+        authorization_list: None,
+        v: Some(v),
+        r: Some(r),
+        s: Some(s),
+        from,
+        hash,
+        signing_hash: Some(signing_hash),
+        source_hash: None,
+        mint: None,
+        is_system_tx: None,
+    })
+}
+
+// Blob transactions arrive either as the bare payload or wrapped as [payload, blobs, commitments, proofs]
+fn decode_eip4844_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let rlp = Rlp::new(raw_tx);
+    let item_count = list_item_count(&rlp)?;
+
+    let first = rlp.at(0).map_err(|error| FieldDecodeError { field: "transaction envelope", error })?;
+    if !first.is_list() {
+        return decode_eip4844_payload(raw_tx, None);
+    }
+
+    if item_count != 4 {
+        return Err(format!("Expected 4 items for EIP-4844 network wrapper, got {}", item_count).into());
+    }
+
+    let sidecar = BlobSidecar {
+        blobs: list_field_at(&rlp, 1, "blobs")?,
+        commitments: list_field_at(&rlp, 2, "commitments")?,
+        proofs: list_field_at(&rlp, 3, "proofs")?,
+    };
+    decode_eip4844_payload(first.as_raw(), Some(sidecar))
+}
+
+fn decode_eip4844_payload(raw_tx: &[u8], sidecar: Option<BlobSidecar>) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let rlp = Rlp::new(raw_tx);
+
+    let item_count = list_item_count(&rlp)?;
+
+    if item_count != 14 {
+        return Err(format!("Expected 14 items for EIP-4844 transaction, got {}", item_count).into());
+    }
+
+    let to = optional_address_at(&rlp, 5)?;
+    if to.is_none() {
+        return Err("Failed to decode to: blob transactions cannot create contracts".into());
+    }
+
+    let blob_versioned_hashes: Vec<H256> = list_field_at(&rlp, 10, "blob_versioned_hashes")?;
+    if let Some(sidecar) = &sidecar {
+        verify_blob_sidecar(&blob_versioned_hashes, sidecar)?;
+    }
+
+    let v: U256 = field_at(&rlp, 11, "v")?;
+    let r: U256 = field_at(&rlp, 12, "r")?;
+    let s: U256 = field_at(&rlp, 13, "s")?;
+    // The hash covers only the payload, never the blobs of the network wrapper
+    let hash = H256(utils::keccak256(&[&[0x03], raw_tx].concat()));
+    let signing_hash = H256(utils::keccak256(&signing_payload(Some(0x03), &rlp, 11, None)?));
+    let from = if v.bits() <= 64 { recover_sender(&signing_hash, v.low_u64(), r, s).ok() } else { None };
+
+    Ok(Transaction {
+        transaction_type: TransactionType::EIP4844,
+        chain_id: Some(field_at(&rlp, 0, "chain_id")?),
+        nonce: Some(field_at(&rlp, 1, "nonce")?),
+        gas_price: None,
+        max_priority_fee_per_gas: Some(field_at(&rlp, 2, "max_priority_fee_per_gas")?),
+        max_fee_per_gas: Some(field_at(&rlp, 3, "max_fee_per_gas")?),
+        gas_limit: field_at(&rlp, 4, "gas_limit")?,
+        to,
+        value: field_at(&rlp, 6, "value")?,
+        data: field_at(&rlp, 7, "data")?,
+        access_list: Some(field_at(&rlp, 8, "access_list")?),
+        max_fee_per_blob_gas: Some(field_at(&rlp, 9, "max_fee_per_blob_gas")?),
+        blob_versioned_hashes: Some(blob_versioned_hashes),
+        blob_sidecar: sidecar,
+        authorization_list: None,
+        v: Some(v),
+        r: Some(r),
+        s: Some(s),
+        from,
+        hash,
+        signing_hash: Some(signing_hash),
+        source_hash: None,
+        mint: None,
+        is_system_tx: None,
+    })
+}
+
+fn decode_eip7702_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let rlp = Rlp::new(raw_tx);
+
+    let item_count = list_item_count(&rlp)?;
+
+    if item_count != 13 {
+        return Err(format!("Expected 13 items for EIP-7702 transaction, got {}", item_count).into());
+    }
+
+    let to = optional_address_at(&rlp, 5)?;
+    if to.is_none() {
+        return Err("Failed to decode to: set-code transactions cannot create contracts".into());
+    }
+
+    let v: U256 = field_at(&rlp, 10, "v")?;
+    let r: U256 = field_at(&rlp, 11, "r")?;
+    let s: U256 = field_at(&rlp, 12, "s")?;
+    let hash = H256(utils::keccak256(&[&[0x04], raw_tx].concat()));
+    let signing_hash = H256(utils::keccak256(&signing_payload(Some(0x04), &rlp, 10, None)?));
+    let from = if v.bits() <= 64 { recover_sender(&signing_hash, v.low_u64(), r, s).ok() } else { None };
+
+    Ok(Transaction {
+        transaction_type: TransactionType::EIP7702,
+        chain_id: Some(field_at(&rlp, 0, "chain_id")?),
+        nonce: Some(field_at(&rlp, 1, "nonce")?),
+        gas_price: None,
+        max_priority_fee_per_gas: Some(field_at(&rlp, 2, "max_priority_fee_per_gas")?),
+        max_fee_per_gas: Some(field_at(&rlp, 3, "max_fee_per_gas")?),
+        gas_limit: field_at(&rlp, 4, "gas_limit")?,
+        to,
+        value: field_at(&rlp, 6, "value")?,
+        data: field_at(&rlp, 7, "data")?,
+        access_list: Some(field_at(&rlp, 8, "access_list")?),
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: None,
+        blob_sidecar: None,
+        authorization_list: Some(field_at(&rlp, 9, "authorization_list")?),
+        v: Some(v),
+        r: Some(r),
+        s: Some(s),
+        from,
+        hash,
+        signing_hash: Some(signing_hash),
+        source_hash: None,
+        mint: None,
+        is_system_tx: None,
+    })
+}
+
+// OP-Stack deposits are derived from L1 events rather than signed, so there is no
+// signature or nonce; the sender is carried explicitly in the payload
+fn decode_deposit_transaction(raw_tx: &[u8]) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let rlp = Rlp::new(raw_tx);
+
+    let item_count = list_item_count(&rlp)?;
+
+    if item_count != 8 {
+        return Err(format!("Expected 8 items for deposit transaction, got {}", item_count).into());
+    }
+
+    let to = optional_address_at(&rlp, 2)?;
+    let hash = H256(utils::keccak256(&[&[0x7e], raw_tx].concat()));
+
+    Ok(Transaction {
+        transaction_type: TransactionType::Deposit,
+        chain_id: None,
+        nonce: None,
         gas_price: None,
-        max_priority_fee_per_gas: Some(rlp.val_at(2).map_err(|e| format!("Failed to decode max_priority_fee_per_gas: {:?}", e))?),
-        max_fee_per_gas: Some(rlp.val_at(3).map_err(|e| format!("Failed to decode max_fee_per_gas: {:?}", e))?),
-        gas_limit: rlp.val_at(4).map_err(|e| format!("Failed to decode gas_limit: {:?}", e))?,
+        max_priority_fee_per_gas: None,
+        max_fee_per_gas: None,
+        gas_limit: field_at(&rlp, 5, "gas_limit")?,
         to,
-        value: rlp.val_at(6).map_err(|e| format!("Failed to decode value: {:?}", e))?,
-        data: rlp.val_at(7).map_err(|e| format!("Failed to decode data: {:?}", e))?,
-        access_list: Some(rlp.val_at(8).map_err(|e| format!("Failed to decode access_list: {:?}", e))?),
-        v: rlp.val_at(9).map_err(|e| format!("Failed to decode v: {:?}", e))?,
-        r: rlp.val_at(10).map_err(|e| format!("Failed to decode r: {:?}", e))?,
-        s: rlp.val_at(11).map_err(|e| format!("Failed to decode s: {:?}", e))?,
+        value: field_at(&rlp, 4, "value")?,
+        data: field_at(&rlp, 7, "data")?,
+        access_list: None,
+        max_fee_per_blob_gas: None,
+        blob_versioned_hashes: None,
+        blob_sidecar: None,
+        authorization_list: None,
+        v: None,
+        r: None,
+        s: None,
+        from: Some(field_at(&rlp, 1, "from")?),
+        hash,
+        signing_hash: None,
+        source_hash: Some(field_at(&rlp, 0, "source_hash")?),
+        mint: Some(field_at(&rlp, 3, "mint")?),
+        is_system_tx: Some(field_at(&rlp, 6, "is_system_tx")?),
     })
 }
 
-pub fn txn_decoder(tx: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    match decode_transaction(tx) {
-        Ok(decoded) => {
-            println!("Decoded Legacy Transaction:");
-            println!("  Nonce: {:?}", decoded.nonce);
-            println!("  Gas Price: {:?}", decoded.gas_price);
-            println!("  Gas Limit: {:?}", decoded.gas_limit);
-            println!("  To: {:?}", decoded.to.unwrap());
-            println!("  Value: {:?}", decoded.value);
-            println!("  Data length: {} bytes", decoded.data.len());
-            println!("  V: {:?}", decoded.v);
-            println!("  R: {:?}", decoded.r);
-            println!("  S: {:?}", decoded.s);
-            Ok(decoded.data)
-        },
-        Err(e) => Err(e),
-    }
-}
\ No newline at end of file
+// Each versioned hash must be 0x01 followed by the tail of sha256(commitment)
+fn verify_blob_sidecar(versioned_hashes: &[H256], sidecar: &BlobSidecar) -> Result<(), Box<dyn std::error::Error>> {
+    let count = versioned_hashes.len();
+    if sidecar.blobs.len() != count || sidecar.commitments.len() != count || sidecar.proofs.len() != count {
+        return Err(format!(
+            "Blob sidecar has {} blobs, {} commitments and {} proofs for {} versioned hashes",
+            sidecar.blobs.len(), sidecar.commitments.len(), sidecar.proofs.len(), count
+        ).into());
+    }
+
+    for (i, (commitment, versioned_hash)) in sidecar.commitments.iter().zip(versioned_hashes).enumerate() {
+        let mut expected: [u8; 32] = Sha256::digest(commitment).into();
+        expected[0] = 0x01;
+        if versioned_hash.as_bytes() != expected {
+            return Err(format!("Blob commitment {} does not match versioned hash {:?}", i, versioned_hash).into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every signed vector is signed with the key 0x4646..46
+    const SENDER: &str = "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+
+    // The EIP-155 example: nonce 9, 20 gwei, 1 ether to 0x3535..35 on chain 1
+    const EIP155_TX: &str = concat!(
+        "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef",
+        "61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf5",
+        "55c9f3dc64214b297fb1966a3b6d83",
+    );
+    // The same transaction signed without a chain id
+    const LEGACY_TX: &str = concat!(
+        "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000801ba08383",
+        "adc8b8ae116f918fb44ca7ff9dfd8012596a5c130c6246a2cc717ba41cdaa053ddfacf5bd4aa7e46d1575acf52636ea6",
+        "59b91f29e2fb91c75567a279738f38",
+    );
+    // 4 bytes of data and one access list entry, on chain 1
+    const EIP2930_TX: &str = concat!(
+        "0x01f89e01071e82c3509435353535353535353535353535353535353535350584a9059cbbf838f79442424242424242",
+        "42424242424242424242424242e1a0010101010101010101010101010101010101010101010101010101010101010180",
+        "a0188d7275d2cfba9f40280bc1a37c68055aefd4c4d6b26b07c209a2d5576aec29a0647e10a547c8e0dd3954ee5f318c",
+        "f1355d6c38a151143910741aaf29616afbe7",
+    );
+    // A contract creation on chain 1
+    const EIP1559_TX: &str = concat!(
+        "0x02f8520108026482ea6080808460806040c080a06f9341e49919c2b3d14f9e01ec82b33886bfb9e9906ab3f2380f82",
+        "5819043f62a0344c6e98b6055045a42bc0dc4b30bb91503f7f4058d8a9678cef8f1d4e91ffc9",
+    );
+    // One blob, commitment 0xc0..c0
+    const EIP4844_TX: &str = concat!(
+        "0x03f888050302648252089435353535353535353535353535353535353535350783010203c009e1a001e2f35246044c",
+        "b2646ad1a00d9373fe9e4c129324952976bc2cecb9c31952b701a0250f4a3662e92b4076545a3e53d56e34cc3839941d",
+        "725dc5b25715bd9970a366a0101c81b5075dca133a5dfc8bdee58eeab6e67acdc49197b49a5559aeabbd1bda",
+    );
+    const EIP4844_NETWORK_TX: &str = concat!(
+        "0x03f8f8f888050302648252089435353535353535353535353535353535353535350783010203c009e1a001e2f35246",
+        "044cb2646ad1a00d9373fe9e4c129324952976bc2cecb9c31952b701a0250f4a3662e92b4076545a3e53d56e34cc3839",
+        "941d725dc5b25715bd9970a366a0101c81b5075dca133a5dfc8bdee58eeab6e67acdc49197b49a5559aeabbd1bdac988",
+        "aaaaaaaaaaaaaaaaf1b0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0",
+        "c0c0c0c0c0c0c0c0c0c0f1b0bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+        "bbbbbbbbbbbbbbbbbbbbbbbb",
+    );
+    // Delegates the code of the key 0x1111..11 to 0x7777..77
+    const EIP7702_TX: &str = concat!(
+        "0x04f8c00103026482c3509435353535353535353535353535353535353535358080c0f85cf85a019477777777777777",
+        "777777777777777777777777770480a0f5075c6df9ae677700727c8a2e04c9c2866094c26d0d5829ee873d47db9568e5",
+        "a053081ee9237fba0d0cf06f8cf4b2a723204815a57fd5d0f9c162b65a175167d201a0a7d9dee67f140543349203d201",
+        "145b47cb20a5b6d26cb8123caaad8b48be8076a0053fba26fa71a111bd242aa90aacf2861a9fcb69d4ef17a233313f66",
+        "7fde0f47",
+    );
+    const DEPOSIT_TX: &str = concat!(
+        "0x7ef867a0abababababababababababababababababababababababababababababababab94dededededededededede",
+        "dededededededededede944242424242424242424242424242424242424242880de0b6b3a7640000880de0b6b3a76400",
+        "00830f42408084015d8eb9",
+    );
+
+    fn h256(hex: &str) -> H256 {
+        H256::from_slice(&hex::decode(utils::remove_0x_prefix(hex)).unwrap())
+    }
+
+    fn assert_signed(raw: &str, transaction_type: TransactionType, hash: &str, signing_hash: &str) -> Transaction {
+        let tx = decode_transaction(raw).unwrap();
+        assert_eq!(tx.transaction_type, transaction_type);
+        assert_eq!(tx.hash, h256(hash));
+        assert_eq!(tx.signing_hash, Some(h256(signing_hash)));
+        assert_eq!(tx.from.map(|from| format!("0x{:x}", from)).as_deref(), Some(SENDER));
+        tx
+    }
+
+    #[test]
+    fn legacy_with_chain_id() {
+        let tx = assert_signed(
+            EIP155_TX,
+            TransactionType::Legacy,
+            "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788",
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53",
+        );
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, Some(U256::from(9)));
+        assert_eq!(tx.v, Some(U256::from(37)));
+    }
+
+    #[test]
+    fn legacy_without_chain_id() {
+        let tx = assert_signed(
+            LEGACY_TX,
+            TransactionType::Legacy,
+            "0x9eb247ec381302e0ac0c3c8d8d14969bb49d31ae3d266274d3112e1a86585d94",
+            "0xf9e36c28c8cb35adba138005c02ab7aa7fbcd891f3139cb2eeed052a51cd2713",
+        );
+        assert_eq!(tx.chain_id, None);
+        assert_eq!(tx.v, Some(U256::from(27)));
+    }
+
+    #[test]
+    fn eip2930() {
+        let tx = assert_signed(
+            EIP2930_TX,
+            TransactionType::EIP2930,
+            "0x4f8ef5bf751deb81c3a4d30e9ff4f69856fea359ab31e7783ed92c568a40f2c5",
+            "0xda623221cd3a20afdcf3417a3951dcc30f4def3352a52f34aec3fb315c12da8b",
+        );
+        let access_list = tx.access_list.unwrap();
+        assert_eq!(access_list.0, vec![AccessListItem(H160::repeat_byte(0x42), vec![H256::repeat_byte(0x01)])]);
+        assert_eq!(tx.data, [0xa9, 0x05, 0x9c, 0xbb]);
+    }
+
+    #[test]
+    fn eip1559() {
+        let tx = assert_signed(
+            EIP1559_TX,
+            TransactionType::EIP1559,
+            "0xa4c088f26908475390b421f6ee18959e7ead2bb4e1ff3df32ef50a3728b24497",
+            "0xa26a1992ecf3fb75c2267da9ab2e5383b4e0260b63b2f892ba74c14acb2085ec",
+        );
+        assert_eq!(tx.to, None);
+        assert_eq!(tx.max_priority_fee_per_gas, Some(U256::from(2)));
+        assert_eq!(tx.max_fee_per_gas, Some(U256::from(100)));
+    }
+
+    #[test]
+    fn eip4844_bare_and_network_wrapper() {
+        let hash = "0x59e8d5141598ae9d83d4c42bb3182edfdf9d9b4834b5b10ad4b9adc223618576";
+        let signing_hash = "0x11ba0eff3736ea080dc51ca2e853724da771c901994455955ad78d2210745219";
+        let bare = assert_signed(EIP4844_TX, TransactionType::EIP4844, hash, signing_hash);
+        assert_eq!(bare.max_fee_per_blob_gas, Some(U256::from(9)));
+        assert_eq!(bare.blob_versioned_hashes.as_ref().map(Vec::len), Some(1));
+        assert!(bare.blob_sidecar.is_none());
+
+        // The wrapper is not part of the hash
+        let wrapped = assert_signed(EIP4844_NETWORK_TX, TransactionType::EIP4844, hash, signing_hash);
+        let sidecar = wrapped.blob_sidecar.unwrap();
+        assert_eq!(sidecar.commitments, vec![vec![0xc0; 48]]);
+        assert_eq!(wrapped.blob_versioned_hashes, bare.blob_versioned_hashes);
+    }
+
+    #[test]
+    fn eip4844_sidecar_must_match_the_versioned_hashes() {
+        let tampered = EIP4844_NETWORK_TX.replacen(&"c0".repeat(48), &"c1".repeat(48), 1);
+        assert!(decode_transaction(&tampered).unwrap_err().to_string().contains("does not match versioned hash"));
+    }
+
+    #[test]
+    fn eip7702() {
+        let tx = assert_signed(
+            EIP7702_TX,
+            TransactionType::EIP7702,
+            "0xbe1392ba5313ac55cb0e0be9e4eb1f7825920a8cfa1a2bf62886da096c6aca93",
+            "0x12ed80564426f7e80ebf29077c261196e86c9eaf4e0cffce45a4c1046304caba",
+        );
+        let authorizations = tx.authorization_list.unwrap();
+        assert_eq!(authorizations.0.len(), 1);
+        let authorization = &authorizations.0[0];
+        assert_eq!(authorization.address, H160::repeat_byte(0x77));
+        assert_eq!(authorization.nonce, 4);
+        assert_eq!(
+            authorization.authority.map(|a| format!("0x{:x}", a)).as_deref(),
+            Some("0x19e7e376e7c213b7e7e7e46cc70a5dd086daff2a")
+        );
+    }
+
+    #[test]
+    fn deposit() {
+        let tx = decode_transaction(DEPOSIT_TX).unwrap();
+        assert_eq!(tx.transaction_type, TransactionType::Deposit);
+        assert_eq!(tx.hash, h256("0x7664a867c183799202895332b116dba4a51d5f92bac84d27ad54ca1cac3f6d9c"));
+        assert_eq!(tx.from, Some(H160::repeat_byte(0xde)));
+        assert_eq!(tx.to, Some(H160::repeat_byte(0x42)));
+        assert_eq!(tx.source_hash, Some(H256::repeat_byte(0xab)));
+        assert_eq!(tx.mint, Some(U256::exp10(18)));
+        assert_eq!(tx.is_system_tx, Some(false));
+        // Deposits are neither signed nor nonced
+        assert_eq!((tx.nonce, tx.v, tx.r, tx.s, tx.signing_hash), (None, None, None, None, None));
+    }
+}
@@ -0,0 +1,260 @@
+use ethereum_types::{H160, H256, U256};
+use rlp::RlpStream;
+use secp256k1::{Message, Secp256k1, SecretKey};
+use super::txn_decoder::{AccessList, TransactionType};
+use super::utils;
+
+// Fields needed to build a transaction; which of the optional ones are required depends on the type
+#[derive(Debug)]
+pub struct UnsignedTransaction {
+    pub transaction_type: TransactionType,
+    pub chain_id: Option<u64>,
+    pub nonce: U256,
+    pub gas_price: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub gas_limit: U256,
+    pub to: Option<H160>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub access_list: AccessList,
+}
+
+#[derive(Debug)]
+pub struct EncodedTransaction {
+    // Type byte followed by the RLP payload, ready for eth_sendRawTransaction when signed
+    pub raw: Vec<u8>,
+    pub signing_hash: H256,
+    // Only set once the transaction is signed
+    pub hash: Option<H256>,
+}
+
+impl UnsignedTransaction {
+    fn type_byte(&self) -> Result<Option<u8>, Box<dyn std::error::Error>> {
+        match self.transaction_type {
+            TransactionType::Legacy => Ok(None),
+            TransactionType::EIP2930 => Ok(Some(0x01)),
+            TransactionType::EIP1559 => Ok(Some(0x02)),
+            ref other => Err(format!("Encoding {:?} transactions is not supported", other).into()),
+        }
+    }
+
+    fn require(value: Option<U256>, field: &str) -> Result<U256, Box<dyn std::error::Error>> {
+        value.ok_or_else(|| format!("Missing {} for this transaction type", field).into())
+    }
+
+    fn chain_id(&self) -> Result<u64, Box<dyn std::error::Error>> {
+        self.chain_id.ok_or_else(|| "Missing chain_id for this transaction type".into())
+    }
+
+    // Appends the unsigned fields in the order the transaction type defines them
+    fn append_fields(&self, stream: &mut RlpStream) -> Result<(), Box<dyn std::error::Error>> {
+        match self.transaction_type {
+            TransactionType::Legacy => {
+                stream.append(&self.nonce);
+                stream.append(&Self::require(self.gas_price, "gas_price")?);
+            }
+            TransactionType::EIP2930 => {
+                stream.append(&self.chain_id()?);
+                stream.append(&self.nonce);
+                stream.append(&Self::require(self.gas_price, "gas_price")?);
+            }
+            _ => {
+                stream.append(&self.chain_id()?);
+                stream.append(&self.nonce);
+                stream.append(&Self::require(self.max_priority_fee_per_gas, "max_priority_fee_per_gas")?);
+                stream.append(&Self::require(self.max_fee_per_gas, "max_fee_per_gas")?);
+            }
+        }
+
+        stream.append(&self.gas_limit);
+        match &self.to {
+            Some(to) => stream.append(to),
+            None => stream.append_empty_data(),
+        };
+        stream.append(&self.value);
+        stream.append(&self.data);
+        if !matches!(self.transaction_type, TransactionType::Legacy) {
+            stream.append(&self.access_list);
+        }
+        Ok(())
+    }
+
+    fn field_count(&self) -> usize {
+        match self.transaction_type {
+            TransactionType::Legacy => 6,
+            TransactionType::EIP2930 => 8,
+            _ => 9,
+        }
+    }
+
+    fn with_type_byte(&self, payload: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut raw = Vec::with_capacity(payload.len() + 1);
+        raw.extend(self.type_byte()?);
+        raw.extend_from_slice(payload);
+        Ok(raw)
+    }
+
+    // The payload the sender signs; legacy transactions with a chain id follow EIP-155
+    pub fn signing_payload(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let eip155 = matches!(self.transaction_type, TransactionType::Legacy) && self.chain_id.is_some();
+        let mut stream = RlpStream::new_list(self.field_count() + if eip155 { 3 } else { 0 });
+        self.append_fields(&mut stream)?;
+        if eip155 {
+            stream.append(&self.chain_id()?);
+            stream.append_empty_data();
+            stream.append_empty_data();
+        }
+        self.with_type_byte(&stream.out())
+    }
+
+    pub fn signing_hash(&self) -> Result<H256, Box<dyn std::error::Error>> {
+        Ok(H256(utils::keccak256(&self.signing_payload()?)))
+    }
+
+    // Encodes the transaction with a signature produced elsewhere, `v` being the raw value for the type
+    pub fn encode_signed(&self, v: U256, r: U256, s: U256) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut stream = RlpStream::new_list(self.field_count() + 3);
+        self.append_fields(&mut stream)?;
+        stream.append(&v);
+        stream.append(&r);
+        stream.append(&s);
+        self.with_type_byte(&stream.out())
+    }
+
+    pub fn encode(&self) -> Result<EncodedTransaction, Box<dyn std::error::Error>> {
+        Ok(EncodedTransaction {
+            raw: self.signing_payload()?,
+            signing_hash: self.signing_hash()?,
+            hash: None,
+        })
+    }
+
+    pub fn sign(&self, private_key: &[u8]) -> Result<EncodedTransaction, Box<dyn std::error::Error>> {
+        let secret_key = SecretKey::from_slice(private_key)?;
+        let signing_hash = self.signing_hash()?;
+        let message = Message::from_slice(signing_hash.as_bytes())?;
+        let (recovery_id, compact) = Secp256k1::signing_only()
+            .sign_ecdsa_recoverable(&message, &secret_key)
+            .serialize_compact();
+
+        let parity = recovery_id.to_i32() as u64;
+        let v = match (&self.transaction_type, self.chain_id) {
+            (TransactionType::Legacy, Some(chain_id)) => U256::from(chain_id) * 2 + 35 + parity,
+            (TransactionType::Legacy, None) => U256::from(27 + parity),
+            _ => U256::from(parity),
+        };
+
+        let raw = self.encode_signed(
+            v,
+            U256::from_big_endian(&compact[..32]),
+            U256::from_big_endian(&compact[32..]),
+        )?;
+        Ok(EncodedTransaction {
+            hash: Some(H256(utils::keccak256(&raw))),
+            raw,
+            signing_hash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::txn_decoder::{decode_transaction_bytes, AccessListItem};
+
+    const KEY: [u8; 32] = [0x46; 32];
+
+    // The EIP-155 example transaction, the decoder tests have the same ones signed
+    fn legacy(chain_id: Option<u64>) -> UnsignedTransaction {
+        UnsignedTransaction {
+            transaction_type: TransactionType::Legacy,
+            chain_id,
+            nonce: U256::from(9),
+            gas_price: Some(U256::from(20_000_000_000u64)),
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+            gas_limit: U256::from(21000),
+            to: Some(H160::repeat_byte(0x35)),
+            value: U256::exp10(18),
+            data: Vec::new(),
+            access_list: AccessList(Vec::new()),
+        }
+    }
+
+    fn assert_round_trip(unsigned: &UnsignedTransaction, hash: &str) {
+        let signed = unsigned.sign(&KEY).unwrap();
+        assert_eq!(signed.hash.map(|h| format!("0x{:x}", h)).as_deref(), Some(hash));
+
+        let decoded = decode_transaction_bytes(&signed.raw).unwrap();
+        assert_eq!(decoded.transaction_type, unsigned.transaction_type);
+        assert_eq!(decoded.chain_id, unsigned.chain_id);
+        assert_eq!(decoded.nonce, Some(unsigned.nonce));
+        assert_eq!(decoded.to, unsigned.to);
+        assert_eq!(decoded.value, unsigned.value);
+        assert_eq!(decoded.data, unsigned.data);
+        assert_eq!(decoded.signing_hash, Some(signed.signing_hash));
+        assert_eq!(format!("0x{:x}", decoded.from.unwrap()), "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+    }
+
+    #[test]
+    fn signs_legacy_transactions() {
+        assert_round_trip(&legacy(Some(1)), "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788");
+        assert_round_trip(&legacy(None), "0x9eb247ec381302e0ac0c3c8d8d14969bb49d31ae3d266274d3112e1a86585d94");
+    }
+
+    #[test]
+    fn signs_typed_transactions() {
+        let eip2930 = UnsignedTransaction {
+            transaction_type: TransactionType::EIP2930,
+            chain_id: Some(1),
+            nonce: U256::from(7),
+            gas_price: Some(U256::from(30)),
+            gas_limit: U256::from(50000),
+            value: U256::from(5),
+            data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            access_list: AccessList(vec![AccessListItem(H160::repeat_byte(0x42), vec![H256::repeat_byte(0x01)])]),
+            ..legacy(Some(1))
+        };
+        assert_round_trip(&eip2930, "0x4f8ef5bf751deb81c3a4d30e9ff4f69856fea359ab31e7783ed92c568a40f2c5");
+
+        let eip1559 = UnsignedTransaction {
+            transaction_type: TransactionType::EIP1559,
+            chain_id: Some(1),
+            nonce: U256::from(8),
+            gas_price: None,
+            max_priority_fee_per_gas: Some(U256::from(2)),
+            max_fee_per_gas: Some(U256::from(100)),
+            gas_limit: U256::from(60000),
+            to: None,
+            value: U256::zero(),
+            data: vec![0x60, 0x80, 0x60, 0x40],
+            access_list: AccessList(Vec::new()),
+        };
+        assert_round_trip(&eip1559, "0xa4c088f26908475390b421f6ee18959e7ead2bb4e1ff3df32ef50a3728b24497");
+    }
+
+    #[test]
+    fn unsigned_encoding_is_the_signing_payload() {
+        let unsigned = legacy(Some(1));
+        let encoded = unsigned.encode().unwrap();
+        assert_eq!(encoded.raw, unsigned.signing_payload().unwrap());
+        assert_eq!(format!("0x{:x}", encoded.signing_hash), "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53");
+        assert!(encoded.hash.is_none());
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        let missing_fee = UnsignedTransaction { transaction_type: TransactionType::EIP1559, ..legacy(Some(1)) };
+        assert!(missing_fee.sign(&KEY).unwrap_err().to_string().contains("max_priority_fee_per_gas"));
+        let missing_chain = UnsignedTransaction { transaction_type: TransactionType::EIP2930, ..legacy(None) };
+        assert!(missing_chain.encode().unwrap_err().to_string().contains("chain_id"));
+        let blob = UnsignedTransaction {
+            transaction_type: TransactionType::EIP4844,
+            max_priority_fee_per_gas: Some(U256::from(2)),
+            max_fee_per_gas: Some(U256::from(100)),
+            ..legacy(Some(1))
+        };
+        assert!(blob.encode().unwrap_err().to_string().contains("not supported"));
+    }
+}
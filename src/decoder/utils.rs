@@ -1,7 +1,293 @@
+use ethabi::{ParamType, Token};
+use ethereum_types::U256;
+use ethers::types::I256;
+use serde_json::{json, Value};
+use tiny_keccak::{Hasher, Keccak};
+use super::function_decoder::ParamLabel;
+
 pub fn remove_0x_prefix(hex_string: &str) -> &str {
     if hex_string.starts_with("0x") || hex_string.starts_with("0X") {
         &hex_string[2..]
     } else {
         hex_string
     }
-}
\ No newline at end of file
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+// JSON form of a decoded token: numbers as decimal strings, byte values as 0x-hex
+pub fn token_to_json(kind: &ParamType, token: &Token) -> Value {
+    labeled_token_to_json(kind, token, None, &[])
+}
+
+// Same as token_to_json, but tuples whose components are all named become objects in declaration order.
+// `abi_type` is the declared type when `kind` is only its encoding (see ParamLabel::abi_type): fixed-point
+// numbers are shown as decimals and function pointers as their address and selector.
+pub fn labeled_token_to_json(kind: &ParamType, token: &Token, abi_type: Option<&str>, components: &[ParamLabel]) -> Value {
+    let fixed_point = abi_type.filter(|t| t.starts_with("fixed") || t.starts_with("ufixed"));
+    match (kind, token) {
+        (_, Token::Int(value)) | (_, Token::Uint(value)) if fixed_point.is_some() => {
+            let abi_type = fixed_point.unwrap_or_default();
+            let decimals = abi_type.rsplit('x').next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            json!(format_fixed(*value, !abi_type.starts_with('u'), decimals))
+        }
+        (_, Token::FixedBytes(bytes)) if abi_type == Some("function") && bytes.len() == 24 => json!({
+            "address": format!("0x{}", hex::encode(&bytes[..20])),
+            "selector": format!("0x{}", hex::encode(&bytes[20..])),
+        }),
+        (_, Token::Address(address)) => json!(format!("0x{:x}", address)),
+        (_, Token::Uint(uint)) => json!(uint.to_string()),
+        (_, Token::Int(int)) => json!(I256::from_raw(*int).to_string()),
+        (_, Token::Bool(b)) => json!(b),
+        (_, Token::String(s)) => json!(s),
+        (_, Token::Bytes(bytes)) | (_, Token::FixedBytes(bytes)) => json!(format!("0x{}", hex::encode(bytes))),
+        // Arrays of tuples carry their element's component names
+        (ParamType::Array(inner), Token::Array(tokens)) | (ParamType::FixedArray(inner, _), Token::FixedArray(tokens)) => {
+            let element_type = abi_type.and_then(element_type);
+            Value::Array(tokens.iter().map(|t| labeled_token_to_json(inner, t, element_type, components)).collect())
+        }
+        (ParamType::Tuple(kinds), Token::Tuple(tokens)) => {
+            let labels = (0..kinds.len()).map(|i| components.get(i).cloned().unwrap_or_default()).collect::<Vec<_>>();
+            let values = kinds
+                .iter()
+                .zip(tokens)
+                .zip(&labels)
+                .map(|((k, t), label)| labeled_token_to_json(k, t, label.abi_type.as_deref(), &label.components));
+            if !labels.is_empty() && labels.iter().all(|label| label.name.is_some()) {
+                Value::Object(labels.iter().map(|label| label.name.clone().unwrap_or_default()).zip(values).collect())
+            } else {
+                Value::Array(values.collect())
+            }
+        }
+        // Shape mismatch between type and token, fall back to the token alone
+        (_, Token::Array(tokens)) | (_, Token::FixedArray(tokens)) | (_, Token::Tuple(tokens)) => {
+            Value::Array(tokens.iter().map(|t| token_to_json(&ParamType::Bool, t)).collect())
+        }
+    }
+}
+
+// "fixed128x18[2][]" holds "fixed128x18[2]"
+fn element_type(array_type: &str) -> Option<&str> {
+    array_type.rfind('[').map(|i| &array_type[..i])
+}
+
+// Helper function to convert ethabi::Token to a more readable format
+pub fn token_to_string(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("Address: 0x{:x}", address),
+        Token::Uint(uint) => format!("Uint: {}", uint),
+        Token::Int(int) => format!("Int: {}", int),
+        Token::Bool(b) => format!("Bool: {}", b),
+        Token::String(s) => format!("String: {}", s),
+        Token::Bytes(bytes) => format!("Bytes: 0x{}", hex::encode(bytes)),
+        Token::FixedBytes(bytes) => format!("FixedBytes: 0x{}", hex::encode(bytes)),
+        Token::Array(tokens) => format!("Array: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
+        Token::FixedArray(tokens) => format!("FixedArray: [{}]", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
+        Token::Tuple(tokens) => format!("Tuple: ({})", tokens.iter().map(token_to_string).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+// A raw fixed-point value with `decimals` digits after the point, e.g. -1500 with 3 decimals is -1.500
+pub fn format_fixed(value: U256, signed: bool, decimals: usize) -> String {
+    let (negative, magnitude) = match signed {
+        true => {
+            let value = I256::from_raw(value);
+            (value.is_negative(), value.unsigned_abs())
+        }
+        false => (false, value),
+    };
+    let digits = format!("{:0>width$}", magnitude.to_string(), width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let sign = if negative { "-" } else { "" };
+    match decimals {
+        0 => format!("{}{}", sign, whole),
+        _ => format!("{}{}.{}", sign, whole, fraction),
+    }
+}
+
+// Indented text rendering of a decoded value: tuples, and arrays of tuples, get one line per component
+pub fn format_labeled_param(
+    name: &str,
+    kind: &ParamType,
+    internal_type: Option<&str>,
+    abi_type: Option<&str>,
+    token: &Token,
+    components: &[ParamLabel],
+    indent: usize,
+) -> Vec<String> {
+    let pad = "  ".repeat(indent);
+    let declared = abi_type.map_or_else(|| kind.to_string(), str::to_string);
+    let type_name = match internal_type {
+        Some(internal) if internal.starts_with("struct ") => internal.to_string(),
+        Some(internal) if internal != declared => format!("{}, {}", declared, internal),
+        _ => declared,
+    };
+
+    match (kind, token) {
+        (ParamType::Tuple(kinds), Token::Tuple(tokens)) => {
+            let mut lines = vec![format!("{}{} ({}):", pad, name, type_name)];
+            for (i, (kind, token)) in kinds.iter().zip(tokens).enumerate() {
+                let label = components.get(i).cloned().unwrap_or_default();
+                let name = label.name.clone().unwrap_or_else(|| format!("[{}]", i));
+                lines.extend(format_labeled_param(
+                    &name,
+                    kind,
+                    label.internal_type.as_deref(),
+                    label.abi_type.as_deref(),
+                    token,
+                    &label.components,
+                    indent + 1,
+                ));
+            }
+            lines
+        }
+        (ParamType::Array(inner), Token::Array(tokens)) | (ParamType::FixedArray(inner, _), Token::FixedArray(tokens))
+            if matches!(**inner, ParamType::Tuple(_)) =>
+        {
+            // "struct Foo[]" describes the elements as "struct Foo"
+            let element_internal_type = internal_type.and_then(element_type);
+            let element_abi_type = abi_type.and_then(element_type);
+            let mut lines = vec![format!("{}{} ({}):", pad, name, type_name)];
+            for (i, token) in tokens.iter().enumerate() {
+                let name = format!("[{}]", i);
+                lines.extend(format_labeled_param(&name, inner, element_internal_type, element_abi_type, token, components, indent + 1));
+            }
+            lines
+        }
+        _ => {
+            let value = match labeled_token_to_json(kind, token, abi_type, components) {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            vec![format!("{}{} ({}): {}", pad, name, type_name, value)]
+        }
+    }
+}
+
+// serde helpers so byte fields round-trip as 0x-prefixed hex strings rather than number arrays
+pub mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(super::remove_0x_prefix(&s)).map_err(serde::de::Error::custom)
+    }
+}
+
+pub mod hex_bytes_list {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(list: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(list.iter().map(|bytes| format!("0x{}", hex::encode(bytes))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| hex::decode(super::remove_0x_prefix(s)).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::Address;
+    use crate::decoder::function_decoder::DecodedParam;
+    use crate::decoder::function_decoder_with_abi::CalldataDecoder;
+
+    const ORACLE_ABI: &str = r#"[
+        {"type": "function", "name": "getL2Output", "stateMutability": "view",
+         "inputs": [{"name": "_l2OutputIndex", "type": "uint256"}],
+         "outputs": [{"name": "", "type": "tuple", "internalType": "struct Types.OutputProposal", "components": [
+            {"name": "outputRoot", "type": "bytes32", "internalType": "bytes32"},
+            {"name": "timestamp", "type": "uint128", "internalType": "uint128"},
+            {"name": "l2BlockNumber", "type": "uint128", "internalType": "uint128"}
+         ]}]},
+        {"type": "function", "name": "getBatch", "stateMutability": "view", "inputs": [],
+         "outputs": [{"name": "batch", "type": "tuple", "internalType": "struct Batch", "components": [
+            {"name": "id", "type": "uint256"},
+            {"name": "calls", "type": "tuple[]", "internalType": "struct Call[]", "components": [
+                {"name": "target", "type": "address"},
+                {"name": "data", "type": "bytes"}
+            ]}
+         ]}]}
+    ]"#;
+
+    fn json_of(param: &DecodedParam) -> Value {
+        labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &param.components)
+    }
+
+    fn lines_of(param: &DecodedParam) -> Vec<String> {
+        let name = param.name.as_deref().unwrap_or("[0]");
+        format_labeled_param(name, &param.kind, param.internal_type.as_deref(), param.abi_type.as_deref(), &param.value, &param.components, 0)
+    }
+
+    #[test]
+    fn struct_components_are_labeled() {
+        let output = Token::Tuple(vec![Token::FixedBytes(vec![0xab; 32]), Token::Uint(1_700_000_000.into()), Token::Uint(42.into())]);
+        let data = format!("0x{}", hex::encode(ethabi::encode(&[output])));
+        let decoded = CalldataDecoder::new(ORACLE_ABI).unwrap().decode_output("getL2Output", &data).unwrap();
+        let param = &decoded.values[0];
+        assert_eq!(param.internal_type.as_deref(), Some("struct Types.OutputProposal"));
+
+        let json = json_of(param);
+        assert_eq!(json, json!({"outputRoot": format!("0x{}", "ab".repeat(32)), "timestamp": "1700000000", "l2BlockNumber": "42"}));
+        let keys = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["outputRoot", "timestamp", "l2BlockNumber"]);
+
+        assert_eq!(
+            lines_of(param),
+            [
+                "[0] (struct Types.OutputProposal):".to_string(),
+                format!("  outputRoot (bytes32): 0x{}", "ab".repeat(32)),
+                "  timestamp (uint128): 1700000000".to_string(),
+                "  l2BlockNumber (uint128): 42".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_of_tuples_keep_their_labels() {
+        let call = |byte: u8, data: Vec<u8>| Token::Tuple(vec![Token::Address(Address::repeat_byte(byte)), Token::Bytes(data)]);
+        let batch = Token::Tuple(vec![Token::Uint(7.into()), Token::Array(vec![call(0x11, vec![1]), call(0x22, Vec::new())])]);
+        let data = format!("0x{}", hex::encode(ethabi::encode(&[batch])));
+        let decoded = CalldataDecoder::new(ORACLE_ABI).unwrap().decode_output("getBatch", &data).unwrap();
+        let param = &decoded.values[0];
+
+        assert_eq!(
+            json_of(param),
+            json!({"id": "7", "calls": [
+                {"target": format!("0x{}", "11".repeat(20)), "data": "0x01"},
+                {"target": format!("0x{}", "22".repeat(20)), "data": "0x"},
+            ]})
+        );
+        assert_eq!(
+            lines_of(param),
+            [
+                "batch (struct Batch):".to_string(),
+                "  id (uint256): 7".to_string(),
+                "  calls (struct Call[]):".to_string(),
+                "    [0] (struct Call):".to_string(),
+                format!("      target (address): 0x{}", "11".repeat(20)),
+                "      data (bytes): 0x01".to_string(),
+                "    [1] (struct Call):".to_string(),
+                format!("      target (address): 0x{}", "22".repeat(20)),
+                "      data (bytes): 0x".to_string(),
+            ]
+        );
+
+        // Without labels tuples stay arrays
+        assert_eq!(token_to_json(&param.kind, &param.value)[1][0], json!([format!("0x{}", "11".repeat(20)), "0x01"]));
+    }
+}
@@ -0,0 +1,45 @@
+use actix_web::{web, HttpResponse};
+use ethers::abi::RawAbi;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use txn_decoder::abi_converter::{abi_to_solidity, solidity_to_abi};
+use super::ApiError;
+
+#[derive(Deserialize, Debug)]
+pub struct AbiToSolidityRequest {
+    // Either the ABI array itself, an artifact object with an "abi" key, or either of those as a string
+    pub abi: Value,
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AbiToSolidityResponse {
+    pub solidity: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SolidityToAbiRequest {
+    pub solidity: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SolidityToAbiResponse {
+    pub abi: Value,
+}
+
+pub async fn convert_abi_to_solidity(req: web::Json<AbiToSolidityRequest>) -> Result<HttpResponse, ApiError> {
+    let abi = match &req.abi {
+        Value::String(s) => serde_json::from_str::<RawAbi>(s),
+        other => serde_json::from_value::<RawAbi>(other.clone()),
+    }
+    .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+
+    let name = req.name.as_deref().unwrap_or("IContract");
+    let solidity = abi_to_solidity(abi, name).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(AbiToSolidityResponse { solidity }))
+}
+
+pub async fn convert_solidity_to_abi(req: web::Json<SolidityToAbiRequest>) -> Result<HttpResponse, ApiError> {
+    let abi = solidity_to_abi(&req.solidity).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(SolidityToAbiResponse { abi }))
+}
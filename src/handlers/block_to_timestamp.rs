@@ -0,0 +1,45 @@
+use actix_web::{web, HttpResponse};
+use ethers::providers::{Http, Middleware, Provider};
+use serde::{Deserialize, Serialize};
+use super::ApiError;
+
+const DEFAULT_RPC_URL: &str = "https://eth.llamarpc.com";
+
+// The RPC endpoint is server configuration, never taken from the request, so callers cannot make the
+// server send requests to hosts of their choosing
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BlockToTimestampRequest {
+    pub block_number: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BlockToTimestampResponse {
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub datetime: String,
+}
+
+pub async fn block_to_timestamp(req: web::Json<BlockToTimestampRequest>) -> Result<HttpResponse, ApiError> {
+    // The RPC_URL env var, or a public mainnet endpoint
+    let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+
+    let provider = Provider::<Http>::try_from(rpc_url.as_str())
+        .map_err(|e| ApiError::Upstream(format!("Invalid RPC_URL: {}", e)))?;
+
+    let block = provider
+        .get_block(req.block_number)
+        .await
+        .map_err(|e| ApiError::Upstream(e.to_string()))?
+        .ok_or_else(|| ApiError::BadRequest(format!("Block {} not found", req.block_number)))?;
+
+    let datetime = block
+        .time()
+        .map_err(|e| ApiError::Upstream(format!("Invalid block timestamp: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(BlockToTimestampResponse {
+        block_number: req.block_number,
+        timestamp: block.timestamp.as_u64(),
+        datetime: datetime.to_string(),
+    }))
+}
@@ -0,0 +1,284 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use txn_decoder::decoder::abi_guesser;
+use txn_decoder::decoder::calldata_encoder::{self, EncodeError};
+use txn_decoder::decoder::call_tree::{CallTree, InnerCall};
+use txn_decoder::decoder::function_decoder::{self, Confidence, DecodedCall, DecodedParam};
+use txn_decoder::decoder::function_decoder_with_abi::CalldataDecoder;
+use txn_decoder::decoder::signature_parser;
+use txn_decoder::decoder::strict_decoder::EncodingViolation;
+use txn_decoder::decoder::utils::labeled_token_to_json;
+use super::{abi_json, ApiError};
+
+#[derive(Deserialize, Debug)]
+pub struct DecodeCalldataRequest {
+    pub calldata: String,
+    // ABI JSON, either as an array value or as a string
+    pub abi: Option<Value>,
+    // Also decode calls nested in the arguments (multicall, Safe transactions, universal router commands)
+    #[serde(default)]
+    pub nested: bool,
+    // Guess the parameter types when no signature matches, instead of failing
+    #[serde(default)]
+    pub guess: bool,
+    // Also decode calldata that is not canonically encoded, listing every encoding violation
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EncodeCalldataRequest {
+    // e.g. "transfer(address to, uint256 amount)", used without an ABI
+    pub signature: Option<String>,
+    // ABI JSON, either as an array value or as a string. Requires `function`.
+    pub abi: Option<Value>,
+    // Function name, full signature when it is overloaded, or 0x selector
+    pub function: Option<String>,
+    // One JSON value per param, see calldata_encoder::encode_arguments
+    #[serde(default)]
+    pub args: Vec<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EncodeCalldataResponse {
+    pub signature: String,
+    pub selector: String,
+    pub calldata: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodedArgument {
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    // Only known when decoding with an ABI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_type: Option<String>,
+    // Tuples whose components are all named in the ABI are objects, other tuples are arrays
+    pub value: Value,
+}
+
+impl From<&DecodedParam> for DecodedArgument {
+    fn from(param: &DecodedParam) -> Self {
+        Self {
+            name: param.name.clone(),
+            kind: param.type_name(),
+            internal_type: param.internal_type.clone(),
+            value: labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &param.components),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodeCalldataResponse {
+    pub function_name: String,
+    pub signature: String,
+    // "abi" when decoded against the supplied ABI, "selector" when the signature was looked up,
+    // "router" for universal router commands found by `nested`, "guess" for layouts inferred by `guess`
+    pub source: String,
+    // Only for "selector": how likely the looked-up signature is the right one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    // Other candidate signatures that also decode the calldata exactly, best first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+    pub arguments: Vec<DecodedArgument>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub inner_calls: Vec<InnerCallResponse>,
+    // Only with `strict`, empty when the calldata is canonically encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violations: Option<Vec<EncodingViolation>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct InnerCallResponse {
+    // Where the payload sits in the outer call, e.g. "data" or "transactions[0]"
+    pub path: String,
+    pub to: Option<String>,
+    pub value: Option<String>,
+    pub delegate_call: bool,
+    pub data: String,
+    // Missing when the payload could not be decoded
+    pub call: Option<Box<DecodeCalldataResponse>>,
+}
+
+pub async fn decode_calldata(req: web::Json<DecodeCalldataRequest>) -> Result<HttpResponse, ApiError> {
+    let calldata = req.calldata.trim();
+    if req.strict && (req.nested || req.guess) {
+        return Err(ApiError::BadRequest("strict cannot be combined with nested or guess".to_string()));
+    }
+    if req.nested && req.guess {
+        return Err(ApiError::BadRequest("nested cannot be combined with guess".to_string()));
+    }
+    if req.strict {
+        return decode_strict(&req).await;
+    }
+    if req.nested {
+        return decode_call_tree(&req).await;
+    }
+
+    let (decoded, source, confidence, alternatives) = match &req.abi {
+        Some(abi) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            let decoded = decoder.decode_call(calldata).map_err(|e| ApiError::Decode {
+                message: e.to_string(),
+                field: None,
+            })?;
+            (decoded, "abi", None, Vec::new())
+        }
+        None => {
+            let lookup = function_decoder::default_signature_lookup().map_err(|e| ApiError::Upstream(e.to_string()))?;
+            let candidates = function_decoder::decode_calldata_candidates(calldata, lookup).await;
+            let mut candidates = match candidates {
+                Err(_) if req.guess => {
+                    let guessed = abi_guesser::guess_calldata(calldata).map_err(|e| ApiError::Decode {
+                        message: e.to_string(),
+                        field: Some("calldata".to_string()),
+                    })?;
+                    let mut response = DecodeCalldataResponse::new(guessed.call, "guess");
+                    response.confidence = Some(guessed.confidence);
+                    return Ok(HttpResponse::Ok().json(response));
+                }
+                result => result.map_err(|e| ApiError::Decode {
+                    message: e.to_string(),
+                    field: None,
+                })?,
+            };
+            let best = candidates.remove(0);
+            let alternatives = candidates.into_iter().map(|candidate| candidate.call.signature).collect();
+            (best.call, "selector", Some(best.confidence), alternatives)
+        }
+    };
+
+    let mut response = DecodeCalldataResponse::new(decoded, source);
+    response.confidence = confidence;
+    response.alternatives = alternatives;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn encode_calldata(req: web::Json<EncodeCalldataRequest>) -> Result<HttpResponse, ApiError> {
+    // Points at the offending argument, e.g. "args[1]"
+    let encode_error = |e: &EncodeError| ApiError::Decode {
+        message: e.to_string(),
+        field: Some(e.argument.map_or_else(|| "args".to_string(), |i| format!("args[{}]", i))),
+    };
+
+    let (signature, calldata) = match (&req.abi, &req.function, &req.signature) {
+        (Some(abi), Some(function), _) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            decoder.encode_call(function, &req.args).map_err(|e| match e.downcast_ref::<EncodeError>() {
+                Some(e) => encode_error(e),
+                None => ApiError::Decode { message: e.to_string(), field: Some("function".to_string()) },
+            })?
+        }
+        (Some(_), None, _) => return Err(ApiError::BadRequest("`function` is required with `abi`".to_string())),
+        (None, _, Some(signature)) => {
+            let signature = signature_parser::parse_signature(signature).map_err(|e| ApiError::Decode {
+                message: format!("Invalid signature: {}", e),
+                field: Some("signature".to_string()),
+            })?;
+            let calldata = calldata_encoder::encode_call(&signature, &req.args).map_err(|e| encode_error(&e))?;
+            (signature.canonical(), calldata)
+        }
+        (None, _, None) => return Err(ApiError::BadRequest("Either `abi` and `function` or `signature` is required".to_string())),
+    };
+
+    Ok(HttpResponse::Ok().json(EncodeCalldataResponse {
+        signature,
+        selector: format!("0x{}", hex::encode(&calldata[..4])),
+        calldata: format!("0x{}", hex::encode(&calldata)),
+    }))
+}
+
+async fn decode_strict(req: &DecodeCalldataRequest) -> Result<HttpResponse, ApiError> {
+    let decode_error = |message: String| ApiError::Decode { message, field: Some("calldata".to_string()) };
+    let calldata = req.calldata.trim();
+
+    let response = match &req.abi {
+        Some(abi) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            let (decoded, violations) = decoder.decode_call_strict(calldata).map_err(|e| decode_error(e.to_string()))?;
+            let mut response = DecodeCalldataResponse::new(decoded, "abi");
+            response.violations = Some(violations);
+            response
+        }
+        None => {
+            let lookup = function_decoder::default_signature_lookup().map_err(|e| ApiError::Upstream(e.to_string()))?;
+            let mut candidates = function_decoder::decode_calldata_strict(calldata, lookup)
+                .await
+                .map_err(|e| decode_error(e.to_string()))?;
+            let (best, violations) = candidates.remove(0);
+            let mut response = DecodeCalldataResponse::new(best.call, "selector");
+            response.confidence = Some(best.confidence);
+            response.alternatives = candidates.into_iter().map(|(candidate, _)| candidate.call.signature).collect();
+            response.violations = Some(violations);
+            response
+        }
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+async fn decode_call_tree(req: &DecodeCalldataRequest) -> Result<HttpResponse, ApiError> {
+    let decode_error = |message: String| ApiError::Decode { message, field: Some("calldata".to_string()) };
+    let lookup = function_decoder::default_signature_lookup().map_err(|e| ApiError::Upstream(e.to_string()))?;
+
+    // Without an ABI, the only calls decoded exactly are universal router commands
+    let exact_source = if req.abi.is_some() { "abi" } else { "router" };
+    let tree = match &req.abi {
+        Some(abi) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            decoder.decode_call_tree(req.calldata.trim(), Some(lookup)).await.map_err(|e| decode_error(e.to_string()))?
+        }
+        None => function_decoder::decode_call_tree(req.calldata.trim(), lookup)
+            .await
+            .map_err(|e| decode_error(e.to_string()))?,
+    };
+
+    Ok(HttpResponse::Ok().json(DecodeCalldataResponse::from_tree(tree, exact_source)))
+}
+
+impl DecodeCalldataResponse {
+    // Calls decoded from the ABI, or from a known payload format, have no confidence
+    fn from_tree(tree: CallTree, exact_source: &str) -> Self {
+        let source = if tree.confidence.is_some() { "selector" } else { exact_source };
+        let mut response = Self::new(tree.call, source);
+        response.confidence = tree.confidence;
+        response.inner_calls = tree.inner.into_iter().map(|inner| InnerCallResponse::new(inner, exact_source)).collect();
+        response
+    }
+
+    fn new(decoded: DecodedCall, source: &str) -> Self {
+        Self {
+            function_name: decoded.function_name,
+            signature: decoded.signature,
+            source: source.to_string(),
+            confidence: None,
+            alternatives: Vec::new(),
+            arguments: decoded.params.iter().map(DecodedArgument::from).collect(),
+            inner_calls: Vec::new(),
+            violations: None,
+        }
+    }
+}
+
+impl InnerCallResponse {
+    fn new(inner: InnerCall, exact_source: &str) -> Self {
+        Self {
+            path: inner.path,
+            to: inner.to.map(|to| format!("0x{:x}", to)),
+            value: inner.value.map(|value| value.to_string()),
+            delegate_call: inner.delegate_call,
+            data: format!("0x{}", hex::encode(&inner.data)),
+            call: inner.tree.map(|tree| Box::new(DecodeCalldataResponse::from_tree(tree, exact_source))),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use txn_decoder::decoder::event_decoder::{self, DecodedLog, EventDecoder};
+use txn_decoder::decoder::function_decoder::{self, Confidence};
+use txn_decoder::decoder::utils::{self, labeled_token_to_json};
+use super::{abi_json, ApiError};
+
+#[derive(Deserialize, Debug)]
+pub struct DecodeLogRequest {
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub data: String,
+    // ABI JSON, either as an array value or as a string. Without it topic0 is looked up as an event signature
+    pub abi: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodedLogArgument {
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub indexed: bool,
+    // Indexed dynamic values only appear as their keccak hash
+    pub hashed: bool,
+    pub value: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodeLogResponse {
+    pub event_name: String,
+    pub signature: String,
+    pub anonymous: bool,
+    // "abi" or "signature", like the calldata decoder's source
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    // Other signatures or indexed layouts that also decode the log exactly, best first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+    pub arguments: Vec<DecodedLogArgument>,
+}
+
+pub async fn decode_log(req: web::Json<DecodeLogRequest>) -> Result<HttpResponse, ApiError> {
+    let topics = event_decoder::parse_topics(&req.topics).map_err(|e| ApiError::Decode {
+        message: e.to_string(),
+        field: Some("topics".to_string()),
+    })?;
+    let data = hex::decode(utils::remove_0x_prefix(req.data.trim())).map_err(|e| ApiError::Decode {
+        message: format!("Invalid log data: {}", e),
+        field: Some("data".to_string()),
+    })?;
+
+    let response = match &req.abi {
+        Some(abi) => {
+            let abi_json = abi_json(abi);
+            let decoder = EventDecoder::new(&abi_json).map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            let decoded = decoder.decode_log(&topics, &data).map_err(|e| ApiError::Decode {
+                message: e.to_string(),
+                field: None,
+            })?;
+            DecodeLogResponse::new(decoded, "abi")
+        }
+        None => {
+            let lookup = function_decoder::default_signature_lookup().map_err(|e| ApiError::Upstream(e.to_string()))?;
+            let mut candidates = event_decoder::decode_log_candidates(&topics, &data, lookup)
+                .await
+                .map_err(|e| ApiError::Decode {
+                    message: e.to_string(),
+                    field: None,
+                })?;
+            let best = candidates.remove(0);
+            let mut response = DecodeLogResponse::new(best.log, "signature");
+            response.confidence = Some(best.confidence);
+            response.alternatives = candidates.iter().map(|candidate| describe_layout(&candidate.log)).collect();
+            response
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// e.g. "Transfer(address indexed,address,uint256 indexed)", since alternatives can differ only in which params are indexed
+fn describe_layout(log: &DecodedLog) -> String {
+    let params = log
+        .params
+        .iter()
+        .map(|param| if param.indexed { format!("{} indexed", param.type_name()) } else { param.type_name() })
+        .collect::<Vec<_>>();
+    format!("{}({})", log.event_name, params.join(","))
+}
+
+impl DecodeLogResponse {
+    fn new(log: DecodedLog, source: &str) -> Self {
+        Self {
+            event_name: log.event_name,
+            signature: log.signature,
+            anonymous: log.anonymous,
+            source: source.to_string(),
+            confidence: None,
+            alternatives: Vec::new(),
+            arguments: log
+                .params
+                .iter()
+                .map(|param| DecodedLogArgument {
+                    name: param.name.clone(),
+                    kind: param.type_name(),
+                    indexed: param.indexed,
+                    hashed: param.hashed,
+                    value: labeled_token_to_json(&param.kind, &param.value, param.abi_type.as_deref(), &[]),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+pub mod unit_converter;
+pub mod block_to_timestamp;
+pub mod abi_handler;
+pub mod txn_handler;
+pub mod calldata_handler;
+pub mod log_handler;
+pub mod revert_handler;
+pub mod output_handler;
+
+use std::fmt;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Upstream(String),
+    // Input was well-formed JSON but could not be decoded, optionally naming the offending field
+    Decode { message: String, field: Option<String> },
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(msg) => write!(f, "{}", msg),
+            ApiError::Upstream(msg) => write!(f, "Upstream error: {}", msg),
+            ApiError::Decode { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Decode { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let field = match self {
+            ApiError::Decode { field, .. } => field.clone(),
+            _ => None,
+        };
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            error: self.to_string(),
+            field,
+        })
+    }
+}
+
+// ABIs are accepted either inline as JSON or as a string holding the JSON
+pub fn abi_json(abi: &Value) -> String {
+    match abi {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
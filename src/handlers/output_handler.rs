@@ -0,0 +1,60 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use txn_decoder::decoder::function_decoder::{self, DecodedReturn};
+use txn_decoder::decoder::function_decoder_with_abi::CalldataDecoder;
+use super::calldata_handler::DecodedArgument;
+use super::{abi_json, ApiError};
+
+#[derive(Deserialize, Debug)]
+pub struct DecodeOutputRequest {
+    // Return data of an eth_call
+    pub data: String,
+    // ABI JSON, either as an array value or as a string. Requires `function`.
+    pub abi: Option<Value>,
+    // Function name, full signature when it is overloaded, or 0x selector
+    pub function: Option<String>,
+    // Used without an ABI, e.g. "getReserves() -> (uint112,uint112,uint32)"
+    pub signature: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodeOutputResponse {
+    pub function_name: String,
+    pub signature: String,
+    pub outputs: Vec<DecodedArgument>,
+}
+
+pub async fn decode_output(req: web::Json<DecodeOutputRequest>) -> Result<HttpResponse, ApiError> {
+    let decode_error = |message: String| ApiError::Decode { message, field: Some("data".to_string()) };
+
+    let decoded = match (&req.abi, &req.function, &req.signature) {
+        (Some(abi), Some(function), _) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            decoder.decode_output(function, &req.data).map_err(|e| decode_error(e.to_string()))?
+        }
+        (Some(_), None, _) => return Err(ApiError::BadRequest("`function` is required with `abi`".to_string())),
+        (None, _, Some(signature)) => {
+            let decoder = function_decoder::CalldataDecoder::new(signature).map_err(|e| ApiError::Decode {
+                message: format!("Invalid signature: {}", e),
+                field: Some("signature".to_string()),
+            })?;
+            decoder.decode_output(&req.data).map_err(|e| decode_error(e.to_string()))?
+        }
+        (None, _, None) => return Err(ApiError::BadRequest("Either `abi` and `function` or `signature` is required".to_string())),
+    };
+
+    Ok(HttpResponse::Ok().json(DecodeOutputResponse::from(decoded)))
+}
+
+impl From<DecodedReturn> for DecodeOutputResponse {
+    fn from(output: DecodedReturn) -> Self {
+        Self {
+            function_name: output.function_name,
+            signature: output.signature,
+            outputs: output.values.iter().map(DecodedArgument::from).collect(),
+        }
+    }
+}
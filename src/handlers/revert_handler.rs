@@ -0,0 +1,61 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use txn_decoder::decoder::function_decoder::{self, Confidence};
+use txn_decoder::decoder::function_decoder_with_abi::CalldataDecoder;
+use txn_decoder::decoder::revert_decoder::{self, DecodedRevert, RevertKind};
+use super::calldata_handler::DecodedArgument;
+use super::{abi_json, ApiError};
+
+#[derive(Deserialize, Debug)]
+pub struct DecodeRevertRequest {
+    // Revert data as returned by eth_call / eth_estimateGas errors
+    pub data: String,
+    // ABI JSON with the contract's custom errors, either as an array value or as a string
+    pub abi: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodeRevertResponse {
+    pub kind: RevertKind,
+    pub name: String,
+    pub signature: String,
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    pub arguments: Vec<DecodedArgument>,
+}
+
+pub async fn decode_revert(req: web::Json<DecodeRevertRequest>) -> Result<HttpResponse, ApiError> {
+    let decode_error = |message: String| ApiError::Decode { message, field: Some("data".to_string()) };
+
+    let decoded = match &req.abi {
+        Some(abi) => {
+            let abi_json = abi_json(abi);
+            let decoder = CalldataDecoder::new(&abi_json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid ABI: {}", e)))?;
+            decoder.decode_revert(&req.data).map_err(|e| decode_error(e.to_string()))?
+        }
+        None => {
+            let lookup = function_decoder::default_signature_lookup().map_err(|e| ApiError::Upstream(e.to_string()))?;
+            revert_decoder::decode_revert(&req.data, lookup)
+                .await
+                .map_err(|e| decode_error(e.to_string()))?
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(DecodeRevertResponse::from(decoded)))
+}
+
+impl From<DecodedRevert> for DecodeRevertResponse {
+    fn from(revert: DecodedRevert) -> Self {
+        Self {
+            kind: revert.kind,
+            name: revert.name,
+            signature: revert.signature,
+            reason: revert.reason,
+            confidence: revert.confidence,
+            arguments: revert.params.iter().map(DecodedArgument::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ethereum_types::{H160, H256, U256};
+use ::txn_decoder::decoder::txn_decoder::{self, AccessList, AccessListItem, FieldDecodeError, Transaction, TransactionType};
+use ::txn_decoder::decoder::txn_encoder::UnsignedTransaction;
+use ::txn_decoder::decoder::utils;
+use super::ApiError;
+
+#[derive(Deserialize, Debug)]
+pub struct DecodeTransactionRequest {
+    pub raw_transaction: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccessListEntry {
+    pub address: String,
+    pub storage_keys: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuthorizationEntry {
+    pub chain_id: String,
+    pub address: String,
+    pub nonce: u64,
+    pub y_parity: String,
+    pub r: String,
+    pub s: String,
+    pub authority: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BlobSidecarEntry {
+    pub blobs: Vec<String>,
+    pub commitments: Vec<String>,
+    pub proofs: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodeTransactionResponse {
+    pub transaction_type: String,
+    pub chain_id: Option<u64>,
+    pub nonce: Option<String>,
+    pub gas_price: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub gas_limit: String,
+    pub to: Option<String>,
+    pub value: String,
+    pub data: String,
+    pub access_list: Option<Vec<AccessListEntry>>,
+    pub max_fee_per_blob_gas: Option<String>,
+    pub blob_versioned_hashes: Option<Vec<String>>,
+    pub blob_sidecar: Option<BlobSidecarEntry>,
+    pub authorization_list: Option<Vec<AuthorizationEntry>>,
+    pub v: Option<String>,
+    pub r: Option<String>,
+    pub s: Option<String>,
+    pub from: Option<String>,
+    pub hash: String,
+    pub signing_hash: Option<String>,
+    pub source_hash: Option<String>,
+    pub mint: Option<String>,
+    pub is_system_tx: Option<bool>,
+}
+
+pub async fn decode_transaction(req: web::Json<DecodeTransactionRequest>) -> Result<HttpResponse, ApiError> {
+    let tx = txn_decoder::decode_transaction(req.raw_transaction.trim()).map_err(|e| ApiError::Decode {
+        message: e.to_string(),
+        field: e.downcast_ref::<FieldDecodeError>().map(|e| e.field.to_string()),
+    })?;
+    Ok(HttpResponse::Ok().json(DecodeTransactionResponse::from(tx)))
+}
+
+// Signing stays with the library and the CLI, the server never sees a private key
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EncodeTransactionRequest {
+    // "legacy", "eip2930" or "eip1559"
+    pub transaction_type: String,
+    pub chain_id: Option<u64>,
+    pub nonce: String,
+    pub gas_price: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub gas_limit: String,
+    pub to: Option<String>,
+    pub value: Option<String>,
+    pub data: Option<String>,
+    pub access_list: Option<Vec<AccessListEntry>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EncodeTransactionResponse {
+    // Unsigned payload, whose hash is what the sender signs
+    pub raw_transaction: String,
+    pub signing_hash: String,
+}
+
+pub async fn encode_transaction(req: web::Json<EncodeTransactionRequest>) -> Result<HttpResponse, ApiError> {
+    let encoded = unsigned_transaction(&req)?
+        .encode()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(EncodeTransactionResponse {
+        raw_transaction: format!("0x{}", hex::encode(&encoded.raw)),
+        signing_hash: format!("0x{:x}", encoded.signing_hash),
+    }))
+}
+
+fn unsigned_transaction(req: &EncodeTransactionRequest) -> Result<UnsignedTransaction, ApiError> {
+    let transaction_type = match req.transaction_type.to_lowercase().as_str() {
+        "legacy" | "0" | "0x0" => TransactionType::Legacy,
+        "eip2930" | "1" | "0x1" => TransactionType::EIP2930,
+        "eip1559" | "2" | "0x2" => TransactionType::EIP1559,
+        other => return Err(ApiError::BadRequest(format!("Unsupported transaction type for encoding: {}", other))),
+    };
+
+    let optional_u256 = |field, value: &Option<String>| value.as_deref().map(|v| parse_u256(field, v)).transpose();
+
+    let access_list = req
+        .access_list
+        .iter()
+        .flatten()
+        .map(|entry| {
+            Ok(AccessListItem(
+                parse_address("access_list", &entry.address)?,
+                entry
+                    .storage_keys
+                    .iter()
+                    .map(|key| parse_storage_key(key))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(UnsignedTransaction {
+        transaction_type,
+        chain_id: req.chain_id,
+        nonce: parse_u256("nonce", &req.nonce)?,
+        gas_price: optional_u256("gas_price", &req.gas_price)?,
+        max_priority_fee_per_gas: optional_u256("max_priority_fee_per_gas", &req.max_priority_fee_per_gas)?,
+        max_fee_per_gas: optional_u256("max_fee_per_gas", &req.max_fee_per_gas)?,
+        gas_limit: parse_u256("gas_limit", &req.gas_limit)?,
+        to: req.to.as_deref().map(|to| parse_address("to", to)).transpose()?,
+        value: optional_u256("value", &req.value)?.unwrap_or_default(),
+        data: req.data.as_deref().map(|data| parse_hex("data", data)).transpose()?.unwrap_or_default(),
+        access_list: AccessList(access_list),
+    })
+}
+
+fn invalid_field(field: &str, message: String) -> ApiError {
+    ApiError::Decode { message, field: Some(field.to_string()) }
+}
+
+// Accepts 0x-prefixed hex or plain decimal
+fn parse_u256(field: &str, value: &str) -> Result<U256, ApiError> {
+    let value = value.trim();
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex_value) => U256::from_str_radix(hex_value, 16).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(value).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| invalid_field(field, format!("Invalid {} {}: {}", field, value, e)))
+}
+
+fn parse_hex(field: &str, value: &str) -> Result<Vec<u8>, ApiError> {
+    hex::decode(utils::remove_0x_prefix(value.trim()))
+        .map_err(|e| invalid_field(field, format!("Invalid hex in {}: {}", field, e)))
+}
+
+fn parse_address(field: &str, value: &str) -> Result<H160, ApiError> {
+    let bytes = parse_hex(field, value)?;
+    if bytes.len() != 20 {
+        return Err(invalid_field(field, format!("Invalid address in {}: expected 20 bytes, got {}", field, bytes.len())));
+    }
+    Ok(H160::from_slice(&bytes))
+}
+
+fn parse_storage_key(value: &str) -> Result<H256, ApiError> {
+    let bytes = parse_hex("access_list", value)?;
+    if bytes.len() != 32 {
+        return Err(invalid_field("access_list", format!("Invalid storage key: expected 32 bytes, got {}", bytes.len())));
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+impl From<Transaction> for DecodeTransactionResponse {
+    fn from(tx: Transaction) -> Self {
+        let transaction_type = match tx.transaction_type {
+            TransactionType::Legacy => "legacy",
+            TransactionType::EIP2930 => "eip2930",
+            TransactionType::EIP1559 => "eip1559",
+            TransactionType::EIP4844 => "eip4844",
+            TransactionType::EIP7702 => "eip7702",
+            TransactionType::Deposit => "deposit",
+        };
+
+        Self {
+            transaction_type: transaction_type.to_string(),
+            chain_id: tx.chain_id,
+            nonce: tx.nonce.map(|nonce| nonce.to_string()),
+            gas_price: tx.gas_price.map(|v| v.to_string()),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.map(|v| v.to_string()),
+            max_fee_per_gas: tx.max_fee_per_gas.map(|v| v.to_string()),
+            gas_limit: tx.gas_limit.to_string(),
+            to: tx.to.map(|to| format!("0x{:x}", to)),
+            value: tx.value.to_string(),
+            data: format!("0x{}", hex::encode(&tx.data)),
+            access_list: tx.access_list.map(|list| {
+                list.0
+                    .into_iter()
+                    .map(|item| AccessListEntry {
+                        address: format!("0x{:x}", item.0),
+                        storage_keys: item.1.iter().map(|key| format!("0x{:x}", key)).collect(),
+                    })
+                    .collect()
+            }),
+            max_fee_per_blob_gas: tx.max_fee_per_blob_gas.map(|v| v.to_string()),
+            blob_versioned_hashes: tx
+                .blob_versioned_hashes
+                .map(|hashes| hashes.iter().map(|hash| format!("0x{:x}", hash)).collect()),
+            blob_sidecar: tx.blob_sidecar.map(|sidecar| {
+                let to_hex = |items: Vec<Vec<u8>>| items.iter().map(|item| format!("0x{}", hex::encode(item))).collect();
+                BlobSidecarEntry {
+                    blobs: to_hex(sidecar.blobs),
+                    commitments: to_hex(sidecar.commitments),
+                    proofs: to_hex(sidecar.proofs),
+                }
+            }),
+            authorization_list: tx.authorization_list.map(|list| {
+                list.0
+                    .into_iter()
+                    .map(|item| AuthorizationEntry {
+                        chain_id: item.chain_id.to_string(),
+                        address: format!("0x{:x}", item.address),
+                        nonce: item.nonce,
+                        y_parity: item.y_parity.to_string(),
+                        r: format!("0x{:064x}", item.r),
+                        s: format!("0x{:064x}", item.s),
+                        authority: item.authority.map(|authority| format!("0x{:x}", authority)),
+                    })
+                    .collect()
+            }),
+            v: tx.v.map(|v| v.to_string()),
+            r: tx.r.map(|r| format!("0x{:064x}", r)),
+            s: tx.s.map(|s| format!("0x{:064x}", s)),
+            from: tx.from.map(|from| format!("0x{:x}", from)),
+            hash: format!("0x{:x}", tx.hash),
+            signing_hash: tx.signing_hash.map(|hash| format!("0x{:x}", hash)),
+            source_hash: tx.source_hash.map(|hash| format!("0x{:x}", hash)),
+            mint: tx.mint.map(|mint| mint.to_string()),
+            is_system_tx: tx.is_system_tx,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use actix_web::{web, HttpResponse};
+use ethers::types::U256;
+use ethers::utils::{format_units, parse_units, Units};
+use serde::{Deserialize, Serialize};
+use super::ApiError;
+
+#[derive(Deserialize, Debug)]
+pub struct ConvertRequest {
+    pub value: String,
+    pub from_unit: String,
+    pub to_unit: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConvertResponse {
+    pub value: String,
+    pub unit: String,
+    pub wei: String,
+}
+
+pub async fn unit_converter(req: web::Json<ConvertRequest>) -> Result<HttpResponse, ApiError> {
+    let result = convert(&req.value, &req.from_unit, &req.to_unit)?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+pub fn convert(value: &str, from_unit: &str, to_unit: &str) -> Result<ConvertResponse, ApiError> {
+    let from: Units = parse_unit(from_unit)?;
+    let to: Units = parse_unit(to_unit)?;
+
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ApiError::BadRequest("Value must not be empty".to_string()));
+    }
+    if value.starts_with('-') {
+        return Err(ApiError::BadRequest("Value must not be negative".to_string()));
+    }
+    if let Some((_, decimals)) = value.split_once('.') {
+        if decimals.len() > from.as_num() as usize {
+            return Err(ApiError::BadRequest(format!(
+                "Value {} has more decimals than {} supports ({})",
+                value, from_unit, from.as_num()
+            )));
+        }
+    }
+
+    let wei: U256 = parse_units(value, from.as_num())
+        .map_err(|e| ApiError::BadRequest(format!("Invalid value {}: {}", value, e)))?
+        .into();
+    let converted = format_units(wei, to.as_num())
+        .map_err(|e| ApiError::BadRequest(format!("Conversion failed: {}", e)))?;
+
+    Ok(ConvertResponse {
+        value: trim_decimals(&converted),
+        unit: to_unit.to_lowercase(),
+        wei: wei.to_string(),
+    })
+}
+
+fn parse_unit(unit: &str) -> Result<Units, ApiError> {
+    unit.trim()
+        .parse::<Units>()
+        .map_err(|_| ApiError::BadRequest(format!("Unknown unit: {}", unit)))
+}
+
+// format_units always pads to the full precision of the unit, e.g. "1.500000000"
+fn trim_decimals(value: &str) -> String {
+    match value.split_once('.') {
+        Some((integer, decimals)) => {
+            let decimals = decimals.trim_end_matches('0');
+            if decimals.is_empty() {
+                integer.to_string()
+            } else {
+                format!("{}.{}", integer, decimals)
+            }
+        }
+        None => value.to_string(),
+    }
+}
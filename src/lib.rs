@@ -0,0 +1,17 @@
+pub mod abi_converter;
+pub mod decoder;
+
+pub use decoder::abi_guesser::{guess_calldata, guess_param_types};
+pub use decoder::calldata_encoder::{encode_call, encode_calldata, EncodeError};
+pub use decoder::call_tree::{CallTree, InnerCall};
+pub use decoder::event_decoder::{decode_log_candidates, DecodedEventParam, DecodedLog, EventDecoder, RankedLog};
+pub use decoder::function_decoder::{
+    decode_call_tree, decode_calldata_candidates, decode_calldata_from_selector, decode_calldata_strict, get_function_signature, Confidence,
+    DecodedCall, DecodedParam, DecodedReturn, ParamLabel, RankedCall,
+};
+pub use decoder::revert_decoder::{decode_revert, DecodedRevert, RevertKind};
+pub use decoder::signature_db::{FourByteSource, LocalSignatureDb, SignatureLookup, SignatureSource};
+pub use decoder::signature_parser::{parse_signature, ParseError, Signature, SignatureParam};
+pub use decoder::strict_decoder::{decode_strict, EncodingViolation, ViolationKind};
+pub use decoder::txn_decoder::{decode_transaction, decode_transaction_bytes, Transaction, TransactionType};
+pub use decoder::txn_encoder::{EncodedTransaction, UnsignedTransaction};
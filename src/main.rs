@@ -1,7 +1,8 @@
-use actix_web::{App, HttpServer, web};
+use actix_web::{App, HttpServer};
 use actix_cors::Cors;
 
 mod routes;
+mod handlers;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -1,8 +1,12 @@
 use actix_web::web;
-use crate::handlers::{unit_converter, block_to_timestamp,abi_handler};
+use crate::handlers::{unit_converter, block_to_timestamp,abi_handler, txn_handler, calldata_handler, log_handler, revert_handler, output_handler, ApiError};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(
+    // Malformed request bodies get the same JSON error shape as handler errors
+    cfg.app_data(web::JsonConfig::default().error_handler(|err, _| {
+        ApiError::BadRequest(err.to_string()).into()
+    }))
+    .service(
         web::resource("/convert")
             .route(web::post().to(unit_converter::unit_converter))
     )
@@ -17,5 +21,33 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     .service(
         web::resource("/convert-solidity-to-abi")
             .route(web::post().to(abi_handler::convert_solidity_to_abi)),
+    )
+    .service(
+        web::resource("/decode-transaction")
+            .route(web::post().to(txn_handler::decode_transaction)),
+    )
+    .service(
+        web::resource("/encode-transaction")
+            .route(web::post().to(txn_handler::encode_transaction)),
+    )
+    .service(
+        web::resource("/decode-calldata")
+            .route(web::post().to(calldata_handler::decode_calldata)),
+    )
+    .service(
+        web::resource("/encode-calldata")
+            .route(web::post().to(calldata_handler::encode_calldata)),
+    )
+    .service(
+        web::resource("/decode-log")
+            .route(web::post().to(log_handler::decode_log)),
+    )
+    .service(
+        web::resource("/decode-revert")
+            .route(web::post().to(revert_handler::decode_revert)),
+    )
+    .service(
+        web::resource("/decode-output")
+            .route(web::post().to(output_handler::decode_output)),
     );
 }
\ No newline at end of file